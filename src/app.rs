@@ -0,0 +1,2907 @@
+// the vic app
+//
+// important functions: init(), update(), view()
+//
+// frames come from frames.rs, and the edit state from timeline.rs,
+// then we add video editing controls/state
+
+use std::error::Error;
+use std::io::{BufRead, Read, Write};
+use std::str::FromStr;
+
+use crossterm::{
+    cursor::{MoveLeft, MoveTo, MoveToColumn, MoveToNextLine, MoveToPreviousLine, MoveToRow},
+    event::{read as await_next_event, Event, KeyCode, KeyEvent, KeyModifiers},
+    execute, queue,
+    style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor, Stylize},
+    terminal,
+};
+
+use crate::error::VicError;
+use crate::frames::{FfmpegSource, FrameIterator, FrameSource, RenderMode, SymbolSet};
+use crate::json::{self, Value};
+use crate::keymap::{Action, Keymap};
+use crate::probe::{self, VideoMetadata};
+use crate::timeline::{
+    assign_lanes, insert_marker, move_marker, next_range_name, remove_marker, ClipRange, Segment,
+};
+use crate::tui::{AppEvents, Msg, Program, UpdateResult, VirtualTerminal};
+use crate::{config, export, ipc, jobs, keymap, recipe, recording, report};
+use crate::{log, Columns, Logs, Rows, Seconds};
+
+// --- MODEL, and other data structures --- //
+
+const NUM_FRAMES_TO_TRACK_FPS: u8 = 10; // arbitrary interval to recalculate fps
+const STATUS_MSG_DURATION: std::time::Duration = std::time::Duration::from_secs(3);
+
+struct Model {
+    terminal_cols: Columns,
+    terminal_rows: Rows,
+    video_path: String,
+    VIDEO_METADATA: VideoMetadata, // const, but not enforcable since Model is mutable
+    frame_iterator: FrameIterator,
+    frame: String,
+
+    // a 24hr video could have 60fps * 60sec/min * 60min/hr * 24hrs = 5_184_000 frames,
+    // which is safely between     u16::MAX = 65_536  and  u32::MAX = 4_294_967_296 frames
+    frame_number: u32,
+
+    paused: bool,
+    looping: bool, // restart at the end, instead of pausing
+    markers: Vec<Seconds>,
+    segments: Vec<Segment>,      // always markers.len() + 1
+    ranges: Vec<ClipRange>,      // in/out ranges, independent of markers
+    pending_in: Option<Seconds>, // in point waiting for an out point
+    history: History,            // for undoing/redoing edits to markers, segments, and ranges
+    speed: f32,
+    hovered_item: Hovering, // current marker or segment
+    hide_controls: bool,
+    show_info: bool,
+    info: Option<Result<probe::ExtendedMetadata, String>>, // probed when first shown
+    is_probing: bool,                                      // in the background, for info
+    keymap: Keymap,
+    needs_to_clear: bool, // if screen resized, we should clear janky screen artifacts
+    status_msg: Option<(String, std::time::Instant)>, // brief feedback, like "undid make marker"
+    label_prompt: Option<String>, // text typed so far, while labeling a segment
+    still_frames: Vec<Seconds>, // pngs to save on finish, only during dry runs
+
+    // for calculating next frame number
+    prev_instant: std::time::Instant,
+    accumulated_time: Seconds,
+
+    // for displaying fps
+    recent_fps: Option<f64>,
+    last_fps_check: std::time::Instant,
+
+    // maybe have derived attributes like:
+    //   frame_number -> position_millis,
+    //   duration_secs -> duration_millis,
+    //   markers<Seconds> -> markers<FrameNumber>,
+    //   markers -> segments,
+    //   duration_secs -> max_frame_number
+    // so computation is not repeated
+
+    // for debugging, to check time elapsed since beginning,
+    // and for timing recorded keys
+    start: std::time::Instant,
+
+    // "now" while replaying keys, so playback between them is the same every replay.
+    // None otherwise, for the real clock
+    replay_time: Option<std::time::Instant>,
+    key_recording: Option<std::fs::File>, // from --record-keys
+    ipc: Option<ipc::IpcServer>,          // from --ipc
+    events: Option<AppEvents<AppEvent>>,  // for background work, set by init()
+
+    export_options: export::ExportOptions, // determines end behavior
+    jobs: Vec<jobs::Job>,                  // planned on quit, see finish()
+    export_error: Option<String>,          // why nothing gets exported, after ctrl+c
+    print_format: Option<report::PrintFormat>, // from --print, also on finish
+}
+
+#[derive(Clone, Copy)]
+enum HoverMode {
+    Markers,
+    Segments,
+}
+
+#[derive(Clone, Copy)]
+struct Hovering {
+    mode: HoverMode,
+    position: usize, // an index in a vec of markers/segments
+}
+
+#[derive(Debug)]
+struct CliArgs {
+    video_filepath: String,
+    max_width: Columns,
+    dry_run: Option<recipe::DryRunFormat>,
+    print_format: Option<report::PrintFormat>,
+    output_template: Option<String>,
+    output_dir: Option<std::path::PathBuf>,
+    preset: Option<export::Preset>,
+    ffmpeg_args: Vec<String>,
+    export_mode: export::ExportMode,
+    export_width: u32,
+    export_fps: u32,
+    export_every: u32,
+    audio_format: export::AudioFormat,
+    concat_audio: bool,
+    jobs: usize,
+    looping: bool,
+    render_mode: RenderMode,
+    symbols: SymbolSet,
+    log_filepath: Option<std::path::PathBuf>,
+    record_keys: Option<std::path::PathBuf>,
+    replay_keys: Option<std::path::PathBuf>,
+    ipc_path: Option<std::path::PathBuf>,
+    //
+    // secret options for now; placeholders for future
+    muted: bool,
+}
+
+// defaults from the config file, for options that are tedious to retype every run.
+// cli args still override them
+#[derive(Debug, Default)]
+struct ConfigDefaults {
+    max_width: Option<Columns>,
+    render_mode: Option<RenderMode>,
+    symbols: Option<SymbolSet>,
+    preset: Option<export::Preset>,
+    output_template: Option<String>,
+    log_filepath: Option<std::path::PathBuf>,
+}
+
+fn read_config_defaults(config_file: &config::ConfigFile) -> Result<ConfigDefaults, String> {
+    // example config.toml:
+    //
+    //   width = 80
+    //   render = "truecolor"
+    //   symbols = "blocky"
+    //   preset = "web-h264"
+    //   output-template = "{stem}_{index:02}.{ext}"
+    //   log = "/tmp/vic_log"
+    //
+    //   [keys]
+    //   ...
+    config_file.check_known(
+        &[
+            "width",
+            "render",
+            "symbols",
+            "preset",
+            "output-template",
+            "log",
+        ],
+        &["keys"],
+    )?;
+    return Ok(ConfigDefaults {
+        max_width: config_file.get_parsed("width")?,
+        render_mode: config_file.get_parsed("render")?,
+        symbols: config_file.get_parsed("symbols")?,
+        preset: config_file.get_parsed("preset")?,
+        output_template: config_file.get_parsed("output-template")?,
+        log_filepath: config_file.get_parsed("log")?,
+    });
+}
+
+// events from other threads, which wake up the event loop
+enum AppEvent {
+    DecoderOutput,            // new ffmpeg error, see FrameIterator::last_decoder_error()
+    Replayed(Seconds, Event), // from --replay-keys, with its time since start
+    ReplayFinished,           // back to the keyboard and the real clock
+    Ipc(ipc::Request),        // from an --ipc client
+    Probed(Result<probe::ExtendedMetadata, String>), // for the info panel
+}
+
+// --- UPDATE --- //
+
+fn update(m: &mut Model, msg: Msg<AppEvent>) -> UpdateResult<VicError> {
+    let result = update_model(m, msg);
+    push_ipc_events(m);
+    return result;
+}
+
+fn update_model(m: &mut Model, msg: Msg<AppEvent>) -> UpdateResult<VicError> {
+    m.needs_to_clear = false;
+    if let Msg::Terminal(event) = &msg {
+        record_event(m, event);
+    }
+    match msg {
+        // ctrl+c quits no matter the keymap, or the label prompt, or a bad output filename
+        Msg::Terminal(Event::Key(keyevent))
+            if keyevent.modifiers == KeyModifiers::CONTROL
+                && keyevent.code == KeyCode::Char('c') =>
+        {
+            if let Err(msg) = plan_export(m) {
+                m.export_error = Some(msg);
+            }
+            return UpdateResult::Finish;
+        }
+        Msg::Terminal(Event::Key(keyevent)) if m.label_prompt.is_some() => {
+            edit_label_prompt(m, keyevent)
+        }
+        Msg::Terminal(Event::Key(keyevent)) => {
+            match m.keymap.action_for(&keyevent) {
+                Some(Action::Quit) => return finish(m),
+                Some(Action::TogglePause) => toggle_paused(m),
+                Some(Action::ToggleHelp) => toggle_controls_visibility(m),
+                Some(Action::ToggleInfo) => toggle_info_visibility(m),
+                Some(Action::SeekBack5s) => seek_backwards_5s(m),
+                Some(Action::SeekForward5s) => seek_forwards_5s(m),
+                Some(Action::SeekBack15s) => seek_backwards_15s(m),
+                Some(Action::SeekForward15s) => seek_forwards_15s(m),
+                Some(Action::SeekToPercent(percent)) => skip_to_percent(m, percent),
+                Some(Action::AdvanceFrame) => advance_one_frame(m),
+                Some(Action::PrevMarker) => goto_prev_marker(m),
+                Some(Action::NextMarker) => goto_next_marker(m),
+                Some(Action::MakeMarker) => create_marker(m),
+                Some(Action::RemoveMarker) => delete_marker(m),
+                Some(Action::NudgeBackFrame) => nudge_marker(m, -1),
+                Some(Action::NudgeForwardFrame) => nudge_marker(m, 1),
+                Some(Action::NudgeBackSec) => {
+                    nudge_marker(m, -(m.VIDEO_METADATA.fps.round() as i32))
+                }
+                Some(Action::NudgeForwardSec) => {
+                    nudge_marker(m, m.VIDEO_METADATA.fps.round() as i32)
+                }
+                Some(Action::ToggleKept) => toggle_segment_kept(m),
+                Some(Action::LabelSegment) => start_label_prompt(m),
+                Some(Action::SetInPoint) => set_in_point(m),
+                Some(Action::SetOutPoint) => set_out_point(m),
+                Some(Action::DeleteRange) => delete_range(m),
+                Some(Action::SaveFrame) => save_still_frame(m),
+                Some(Action::Undo) => undo(m),
+                Some(Action::Redo) => redo(m),
+                None => (),
+            };
+        }
+        Msg::Terminal(Event::Resize(cols, rows)) => {
+            m.terminal_cols = cols;
+            m.terminal_rows = rows;
+            m.needs_to_clear = true;
+        }
+        Msg::Terminal(_) => (),
+        Msg::Tick => (),                         // playback catches up below
+        Msg::App(AppEvent::DecoderOutput) => (), // just redraw, to show the error
+        Msg::App(AppEvent::Replayed(at, event)) => {
+            m.replay_time = Some(m.start + std::time::Duration::from_secs_f64(at));
+            // catch up playback first, like the ticks before a real keypress
+            match update(m, Msg::Tick) {
+                UpdateResult::Continue => (),
+                other => return other,
+            };
+            return update(m, Msg::Terminal(event));
+        }
+        Msg::App(AppEvent::Probed(info)) => {
+            m.info = Some(info);
+            m.is_probing = false;
+            m.needs_to_clear = true;
+        }
+        Msg::App(AppEvent::ReplayFinished) => {
+            m.replay_time = None;
+            m.prev_instant = std::time::Instant::now();
+        }
+        Msg::App(AppEvent::Ipc(request)) => {
+            let result = run_ipc_command(m, &request);
+            // exporting is just like quitting with q, once the outputs are planned
+            let is_exporting = request.command[0].as_str() == Some("export") && result.is_ok();
+            if let Some(ipc) = &m.ipc {
+                ipc.respond(&request, result);
+            }
+            if is_exporting {
+                return UpdateResult::Finish;
+            }
+        }
+    };
+
+    let now = now(m);
+
+    if m.paused {
+        m.prev_instant = now;
+        return UpdateResult::Continue;
+    }
+
+    let whole_elapsed_frames = frames_since_prev_instant(m);
+    let is_too_fast = whole_elapsed_frames == 0;
+    match is_too_fast {
+        true => {
+            // slow down by not drawing anything during this tick
+            m.prev_instant = now;
+            return UpdateResult::Continue;
+        }
+        false => {
+            // now we know the next frame number to render
+            match m.frame_iterator.skip_some_frames(whole_elapsed_frames - 1) {
+                Some(frame) => {
+                    m.frame = frame;
+                    m.frame_number += whole_elapsed_frames as u32;
+                    update_if_moved_past_segment(m);
+                }
+                None => reached_end(m),
+            }
+        }
+    }
+
+    // --- update stats --- //
+
+    let need_to_update_fps =
+        m.frame_iterator.num_frames_rendered % NUM_FRAMES_TO_TRACK_FPS as u32 == 0;
+    if need_to_update_fps {
+        let recent_time_elapsed: Seconds = (now - m.last_fps_check).as_secs_f64();
+        m.recent_fps = Some(NUM_FRAMES_TO_TRACK_FPS as f64 / recent_time_elapsed);
+        m.last_fps_check = now;
+    } else {
+        // Either too early and not enough recent data,
+        // or waiting for next moment to check fps.
+        // No need to constantly update fps every frame.
+    }
+
+    let need_to_log = m.frame_iterator.num_frames_rendered % 100 == 1;
+    if need_to_log {
+        log!(
+            "{:?} {:?} {:?} {:?}",
+            now - m.start,
+            m.prev_instant,
+            m.frame_iterator.num_frames_rendered,
+            m.frame_number
+        );
+    }
+
+    m.prev_instant = now;
+    return UpdateResult::Continue;
+}
+
+// TODO: make these functions associated with model using impl Model {}
+
+fn finish(m: &mut Model) -> UpdateResult<VicError> {
+    // an existing output file, or a label that makes a bad filename, can still be fixed
+    // without losing the session's markers
+    match plan_export(m) {
+        Ok(()) => return UpdateResult::Finish,
+        Err(msg) => {
+            set_status(m, format!("can't export: {}", msg.replace('\n', ". ")));
+            return UpdateResult::Continue;
+        }
+    }
+}
+
+fn plan_export(m: &mut Model) -> Result<(), String> {
+    // figure out all output filenames before leaving the TUI
+    if m.markers.len() == 0 && m.ranges.len() == 0 && m.still_frames.len() == 0 {
+        return Ok(());
+    }
+    m.jobs = export::plan_jobs(
+        &m.video_path,
+        m.VIDEO_METADATA.duration_secs,
+        &m.markers,
+        &m.segments,
+        &m.ranges,
+        &m.still_frames,
+        &m.export_options,
+    )?;
+    return Ok(());
+}
+
+fn now(m: &Model) -> std::time::Instant {
+    // for anything that affects playback, so replays don't depend on how fast they run
+    return m.replay_time.unwrap_or_else(std::time::Instant::now);
+}
+
+fn record_event(m: &mut Model, event: &Event) {
+    // timed like replays, so recording a replay gives back the same file
+    let at = (now(m) - m.start).as_secs_f64();
+    if let (Some(file), Some(line)) = (&mut m.key_recording, recording::format_event(at, event)) {
+        if let Err(e) = writeln!(file, "{}", line) {
+            log!("failed to record keys, so stopped recording: {}", e);
+            m.key_recording = None;
+        }
+    }
+}
+
+fn frames_since_prev_instant(m: &mut Model) -> u32 {
+    // find how many frames elapsed since last tick,
+    // and modify leftover time
+
+    // example converting elapsed ms to elapsed frames:
+    //
+    // 0.040 secs elapsed   30 frames   1.2 frames
+    // ------------------ * --------- =   elapsed
+    //                       1 second
+    //
+    // whole_frames_elapsed = 1 frame
+    //
+    //                0.2 frames    1 second
+    // rounding_err = ---------- * --------- = 0.007 seconds
+    //                             30 frames
+    //
+    let now = now(m);
+    let elapsed_secs = (now - m.prev_instant).as_secs_f64();
+
+    // how many frames should have passed since last tick; sometimes 0, usually 1 or more
+    let mut whole_elapsed_frames: u32 = (elapsed_secs * m.VIDEO_METADATA.fps).floor() as u32;
+
+    // account for accumulated deltas from rounding down.
+    // the leftover time eventually adds up to frame's worth of compensated time
+    // (like an extra day in a leap year)
+    let rounding_err: Seconds =
+        elapsed_secs - (whole_elapsed_frames as f64 * m.VIDEO_METADATA.seconds_per_frame);
+    m.accumulated_time += rounding_err;
+
+    let need_leap_frame = m.accumulated_time > m.VIDEO_METADATA.seconds_per_frame;
+    if need_leap_frame {
+        whole_elapsed_frames += 1;
+        m.accumulated_time -= m.VIDEO_METADATA.seconds_per_frame;
+    }
+
+    // log!("{:?} {:?} {:?} {:?}",
+    //            elapsed_secs,
+    //            whole_elapsed_frames,
+    //            extra_time,
+    //            m.accumulated_time);
+
+    return whole_elapsed_frames;
+}
+
+fn toggle_paused(m: &mut Model) {
+    m.paused = !m.paused;
+    if !m.paused {
+        m.hovered_item.mode = HoverMode::Segments;
+        // no ticks happen while paused, so don't count the paused time as elapsed frames
+        m.prev_instant = now(m);
+    }
+}
+
+fn tick_interval(m: &Model) -> Option<std::time::Duration> {
+    // one tick per frame during playback.
+    // while paused, only wake up to hide an old status msg, else wait for the next keypress
+    if !m.paused {
+        return Some(std::time::Duration::from_secs_f64(
+            m.VIDEO_METADATA.seconds_per_frame / m.speed as f64,
+        ));
+    }
+    return match &m.status_msg {
+        Some((_, instant)) => STATUS_MSG_DURATION.checked_sub(instant.elapsed()),
+        None => None,
+    };
+}
+
+fn toggle_controls_visibility(m: &mut Model) {
+    m.hide_controls = !m.hide_controls;
+    log!("hide controls? {:?}", m.hide_controls);
+    m.needs_to_clear = true; // controls will still show at bottom unless cleared/drawn over
+}
+
+fn toggle_info_visibility(m: &mut Model) {
+    // ffprobe reads the first several seconds of the video for keyframes,
+    // so only probe once, only if asked, and on another thread so playback goes on.
+    // the result comes back as AppEvent::Probed
+    m.show_info = !m.show_info;
+    if m.show_info && m.info.is_none() && !m.is_probing {
+        if let Some(events) = m.events.clone() {
+            let video_path = m.video_path.clone();
+            std::thread::spawn(move || {
+                let info = probe::get_ffprobe_extended_metadata(&video_path)
+                    .map_err(|e| e.to_string().replace('\n', ". "));
+                events.send(AppEvent::Probed(info));
+            });
+            m.is_probing = true;
+        }
+    }
+    m.needs_to_clear = true;
+}
+
+// note: any function that modifies playerhead position aka m.frame_number in Segment mode
+// must check if segment number has changed
+
+fn update_if_moved_past_segment(m: &mut Model) {
+    //
+    // segment    0     1     2
+    //         ┌─────┬─────┬──────┐
+    //         └─────┴─────┴──────┘
+    // marker        0     1
+    //
+    let cur_timestamp = m.frame_number as f64 * m.VIDEO_METADATA.seconds_per_frame;
+    let next_index = m.hovered_item.position;
+    let mut upcoming_markers = m.markers[next_index..].iter();
+    while let Some(next_timestamp) = upcoming_markers.next() {
+        match cur_timestamp > *next_timestamp {
+            true => m.hovered_item.position += 1,
+            false => break,
+        }
+    }
+}
+
+fn update_if_moved_behind_segment(m: &mut Model) {
+    //
+    // segment    0     1     2
+    //         ┌─────┬─────┬──────┐
+    //         └─────┴─────┴──────┘
+    // marker        0     1
+    //
+    let cur_timestamp = m.frame_number as f64 * m.VIDEO_METADATA.seconds_per_frame;
+    let prev_index = m.hovered_item.position;
+    let mut preceding_markers = m.markers[..prev_index].iter().rev();
+    while let Some(prev_timestamp) = preceding_markers.next() {
+        match cur_timestamp < *prev_timestamp {
+            true => m.hovered_item.position -= 1,
+            false => break,
+        }
+    }
+}
+
+fn last_frame_number(m: &Model) -> u32 {
+    // an estimate, since ffprobe's duration can be a little longer than the decoded frames
+    let num_frames = (m.VIDEO_METADATA.duration_secs * m.VIDEO_METADATA.fps).ceil() as u32;
+    return num_frames.saturating_sub(1);
+}
+
+fn seek_to(m: &mut Model, timestamp: Seconds) {
+    // seeking past the end lands on the final frame
+    let timestamp = match m.frame_number > last_frame_number(m) {
+        true => {
+            m.frame_number = last_frame_number(m);
+            m.frame_number as Seconds / m.VIDEO_METADATA.fps
+        }
+        false => timestamp,
+    };
+
+    // a failed seek shouldn't end the whole session,
+    // so explain in the status line and keep showing the last good frame
+    match m.frame_iterator.goto_timestamp(timestamp) {
+        Ok(Some(frame)) => m.frame = frame,
+        Ok(None) => goto_final_frame(m),
+        Err(e) => {
+            log!("failed to seek to {}: {:?}", timestamp, e);
+            set_status(
+                m,
+                format!("failed to seek: {}", e.to_string().replace('\n', ". ")),
+            );
+        }
+    }
+}
+
+fn goto_final_frame(m: &mut Model) {
+    // when the estimated last frame has nothing to decode,
+    // decode the last second instead and keep whichever frame comes last
+    //
+    //             estimate
+    //   ──────────────┼──┤
+    //      ├──────────┤  ^
+    //      last second   actual end
+    let start = (m.VIDEO_METADATA.duration_secs - 1.0).max(0.0);
+    let mut frame_number = (start * m.VIDEO_METADATA.fps) as u32;
+    match m.frame_iterator.goto_timestamp(start) {
+        Ok(Some(mut frame)) => {
+            while let Some(next_frame) = m.frame_iterator.take_frame() {
+                frame = next_frame;
+                frame_number += 1;
+            }
+            m.frame = frame;
+            m.frame_number = frame_number;
+        }
+        Ok(None) => log!("no frames in the last second of video"),
+        Err(e) => log!("failed to find the final frame: {:?}", e),
+    }
+    m.paused = true;
+}
+
+fn reached_end(m: &mut Model) {
+    // the decoder ran out of frames during playback,
+    // so m.frame_number is still the last frame shown
+    match m.looping {
+        true => {
+            m.frame_number = 0;
+            seek_to(m, 0.0);
+            update_if_moved_behind_segment(m);
+        }
+        false => {
+            m.paused = true;
+            set_status(m, "end of video".to_string());
+        }
+    }
+}
+
+fn seek_backwards_5s(m: &mut Model) {
+    let frames_to_backtrack = (m.VIDEO_METADATA.fps * 5.0) as u32;
+    m.frame_number = std::cmp::max(m.frame_number as i32 - frames_to_backtrack as i32, 0) as u32;
+    let timestamp = m.frame_number as Seconds / m.VIDEO_METADATA.fps;
+    seek_to(m, timestamp);
+
+    update_if_moved_behind_segment(m);
+    m.hovered_item.mode = HoverMode::Segments;
+}
+
+fn seek_forwards_5s(m: &mut Model) {
+    let frames_to_skip = (m.VIDEO_METADATA.fps * 5.0) as u32;
+    m.frame_number += frames_to_skip;
+    let timestamp = m.frame_number as Seconds / m.VIDEO_METADATA.fps;
+    seek_to(m, timestamp);
+
+    update_if_moved_past_segment(m);
+    m.hovered_item.mode = HoverMode::Segments;
+}
+
+fn seek_backwards_15s(m: &mut Model) {
+    let frames_to_backtrack = (m.VIDEO_METADATA.fps * 15.0) as u32;
+    m.frame_number = std::cmp::max(m.frame_number as i32 - frames_to_backtrack as i32, 0) as u32;
+    let timestamp = m.frame_number as Seconds / m.VIDEO_METADATA.fps;
+    seek_to(m, timestamp);
+
+    update_if_moved_behind_segment(m);
+    m.hovered_item.mode = HoverMode::Segments;
+}
+
+fn seek_forwards_15s(m: &mut Model) {
+    let frames_to_skip = (m.VIDEO_METADATA.fps * 15.0) as u32;
+    m.frame_number += frames_to_skip;
+    let timestamp = m.frame_number as Seconds / m.VIDEO_METADATA.fps;
+    seek_to(m, timestamp);
+
+    update_if_moved_past_segment(m);
+    m.hovered_item.mode = HoverMode::Segments;
+}
+
+fn goto_prev_marker(m: &mut Model) {
+    // enter marker mode and goto nearest backwards timestamp
+    //
+    // segment    0     1     2
+    //         ┌─────┬─────┬──────┐
+    //         └─────┴─────┴──────┘
+    // marker        0     1
+    //
+    let new_position = m.hovered_item.position as i32 - 1;
+    match new_position >= 0 {
+        false => (),
+        true => {
+            m.hovered_item = Hovering {
+                mode: HoverMode::Markers,
+                position: new_position as usize,
+            };
+            let timestamp: Seconds = m.markers[new_position as usize];
+            m.frame_number = (timestamp * m.VIDEO_METADATA.fps) as u32;
+            seek_to(m, timestamp);
+            m.paused = true;
+        }
+    }
+}
+
+fn goto_next_marker(m: &mut Model) {
+    // enter marker mode and goto nearest forwards timestamp
+    //
+    // segment    0     1     2
+    //         ┌─────┬─────┬──────┐
+    //         └─────┴─────┴──────┘
+    // marker        0     1
+    //
+    let new_position = match m.hovered_item.mode {
+        HoverMode::Markers => m.hovered_item.position + 1,
+        HoverMode::Segments => m.hovered_item.position,
+    };
+    match new_position < m.markers.len() {
+        false => (),
+        true => {
+            m.hovered_item = Hovering {
+                mode: HoverMode::Markers,
+                position: new_position,
+            };
+            let timestamp: Seconds = m.markers[new_position];
+            m.frame_number = (timestamp * m.VIDEO_METADATA.fps) as u32;
+            seek_to(m, timestamp);
+            m.paused = true;
+        }
+    };
+}
+
+fn create_marker(m: &mut Model) {
+    // create new marker at current timestamp
+    match m.hovered_item.mode {
+        HoverMode::Markers => (),
+        HoverMode::Segments => {
+            let timestamp: Seconds = m.frame_number as Seconds / m.VIDEO_METADATA.fps;
+            record_edit(
+                m,
+                format!("make marker at {}", format_secs_to_mm_ss(timestamp)),
+            );
+            insert_marker(&mut m.markers, &mut m.segments, timestamp);
+            log!("{:.3}", timestamp);
+        }
+    }
+}
+
+fn delete_marker(m: &mut Model) {
+    // delete current marker and enter segments mode
+    match m.hovered_item.mode {
+        HoverMode::Segments => (),
+        HoverMode::Markers => {
+            let timestamp: Seconds = m.markers[m.hovered_item.position];
+            record_edit(
+                m,
+                format!("delete marker at {}", format_secs_to_mm_ss(timestamp)),
+            );
+            remove_marker(&mut m.markers, &mut m.segments, m.hovered_item.position);
+            m.hovered_item.mode = HoverMode::Segments;
+        }
+    }
+}
+
+fn nudge_marker(m: &mut Model, num_frames: i32) {
+    // move current marker a few frames backwards or forwards, staying in marker mode.
+    // the preview follows the marker, so cut points can be fine-tuned visually
+    match m.hovered_item.mode {
+        HoverMode::Segments => (),
+        HoverMode::Markers => {
+            let timestamp: Seconds = m.markers[m.hovered_item.position];
+            let max_frame_number =
+                (m.VIDEO_METADATA.duration_secs * m.VIDEO_METADATA.fps).floor() as i32;
+            let marker_frame_number = (timestamp * m.VIDEO_METADATA.fps).round() as i32;
+            let new_frame_number = (marker_frame_number + num_frames).clamp(0, max_frame_number);
+            let new_timestamp: Seconds = new_frame_number as Seconds / m.VIDEO_METADATA.fps;
+
+            record_edit(
+                m,
+                format!(
+                    "move marker from {} to {}",
+                    format_secs_to_mm_ss(timestamp),
+                    format_secs_to_mm_ss(new_timestamp)
+                ),
+            );
+            m.hovered_item.position = move_marker(
+                &mut m.markers,
+                &mut m.segments,
+                m.hovered_item.position,
+                new_timestamp,
+            );
+            m.frame_number = new_frame_number as u32;
+            seek_to(m, new_timestamp);
+            m.paused = true;
+            log!("nudged marker {:.3} -> {:.3}", timestamp, new_timestamp);
+        }
+    }
+}
+
+fn toggle_segment_kept(m: &mut Model) {
+    // mark current segment to be kept or discarded when cutting on finish
+    match m.hovered_item.mode {
+        HoverMode::Markers => (),
+        HoverMode::Segments => {
+            let position = m.hovered_item.position;
+            let action = match m.segments[position].kept {
+                true => "discard",
+                false => "keep",
+            };
+            record_edit(m, format!("{} segment {}", action, position + 1));
+            m.segments[position].kept = !m.segments[position].kept;
+        }
+    }
+}
+
+fn start_label_prompt(m: &mut Model) {
+    // pause and start typing a label for the current segment.
+    // keypresses go to edit_label_prompt() until enter or esc
+    match m.hovered_item.mode {
+        HoverMode::Markers => (),
+        HoverMode::Segments => {
+            m.paused = true;
+            m.label_prompt = Some(m.segments[m.hovered_item.position].label.clone());
+        }
+    }
+}
+
+fn edit_label_prompt(m: &mut Model, keyevent: KeyEvent) {
+    let text = m
+        .label_prompt
+        .as_mut()
+        .expect("label prompt should be open");
+    match keyevent.code {
+        KeyCode::Char(c) if !keyevent.modifiers.contains(KeyModifiers::CONTROL) => text.push(c),
+        KeyCode::Backspace => {
+            text.pop();
+        }
+        KeyCode::Esc => m.label_prompt = None,
+        KeyCode::Enter => {
+            let label = m.label_prompt.take().unwrap_or_default().trim().to_string();
+            let position = m.hovered_item.position;
+            record_edit(m, format!("label segment {}", position + 1));
+            m.segments[position].label = label;
+        }
+        _ => (),
+    }
+}
+
+fn save_still_frame(m: &mut Model) {
+    // save current frame as a png, at full resolution unlike the preview.
+    // this blocks for a moment while ffmpeg seeks and decodes, which seems ok for a keypress
+    let timestamp: Seconds = m.frame_number as Seconds / m.VIDEO_METADATA.fps;
+    let (outdir, stem) = match export::output_dir_and_stem(&m.video_path, &m.export_options) {
+        Ok(outdir_and_stem) => outdir_and_stem,
+        Err(msg) => return set_status(m, msg),
+    };
+    let outpath = export::still_frame_path(&outdir, &stem, timestamp);
+
+    if m.export_options.dry_run {
+        m.still_frames.push(timestamp);
+        set_status(m, format!("will save {} in recipe", outpath.display()));
+        return;
+    }
+    if outpath.exists() {
+        set_status(m, format!("{} already exists", outpath.display()));
+        return;
+    }
+
+    let mut cmd = export::still_frame_cmd(&m.video_path, timestamp, &outpath);
+    log!("saving still frame: {}", recipe::cmd_to_sh(&cmd));
+    match cmd.output() {
+        Ok(output) if output.status.success() => {
+            set_status(m, format!("saved {}", outpath.display()))
+        }
+        Ok(output) => set_status(m, format!("ffmpeg failed to save frame, {}", output.status)),
+        Err(e) => set_status(m, format!("ffmpeg failed to save frame, {}", e)),
+    }
+}
+
+// in/out ranges are an alternative to markers and segments.
+// markers split the whole video into contiguous segments,
+// but ranges are explicit clips that may overlap or leave gaps, like highlights
+
+fn set_in_point(m: &mut Model) {
+    let timestamp: Seconds = m.frame_number as Seconds / m.VIDEO_METADATA.fps;
+    m.pending_in = Some(timestamp);
+    set_status(
+        m,
+        format!("in point at {}", format_secs_to_mm_ss(timestamp)),
+    );
+    m.needs_to_clear = true;
+}
+
+fn set_out_point(m: &mut Model) {
+    // finish a range started by set_in_point()
+    let out_timestamp: Seconds = m.frame_number as Seconds / m.VIDEO_METADATA.fps;
+    match m.pending_in {
+        None => set_status(m, "set an in point first".to_string()),
+        Some(in_timestamp) if in_timestamp == out_timestamp => {
+            set_status(m, "range would be empty".to_string())
+        }
+        Some(in_timestamp) => {
+            // allow marking out before in, just in case
+            let (start, end) = match in_timestamp < out_timestamp {
+                true => (in_timestamp, out_timestamp),
+                false => (out_timestamp, in_timestamp),
+            };
+            let name = next_range_name(&m.ranges);
+            record_edit(m, format!("add {}", name));
+            m.ranges.push(ClipRange {
+                name: name,
+                start: start,
+                end: end,
+            });
+            m.pending_in = None;
+            m.needs_to_clear = true;
+        }
+    }
+}
+
+fn delete_range(m: &mut Model) {
+    // delete the most recent range under the playerhead
+    let timestamp: Seconds = m.frame_number as Seconds / m.VIDEO_METADATA.fps;
+    match m
+        .ranges
+        .iter()
+        .rposition(|range| range.start <= timestamp && timestamp <= range.end)
+    {
+        None => set_status(m, "no range here".to_string()),
+        Some(index) => {
+            record_edit(m, format!("delete {}", m.ranges[index].name));
+            m.ranges.remove(index);
+            m.needs_to_clear = true;
+        }
+    }
+}
+
+// --- UNDO / REDO --- //
+
+// edits are cheap to snapshot (a few dozen floats at most),
+// so store whole copies of the edit state rather than inverse operations
+struct Snapshot {
+    description: String, // eg. "delete marker at 1:23"
+    markers: Vec<Seconds>,
+    segments: Vec<Segment>,
+    ranges: Vec<ClipRange>,
+    hovered_item: Hovering, // to hover the same marker again, like after undoing a delete
+}
+
+#[derive(Default)]
+struct History {
+    undo_stack: Vec<Snapshot>,
+    redo_stack: Vec<Snapshot>,
+}
+
+fn record_edit(m: &mut Model, description: String) {
+    // call before any edit to markers or segments, so it can be undone later
+    let snapshot = Snapshot {
+        description: description,
+        markers: m.markers.clone(),
+        segments: m.segments.clone(),
+        ranges: m.ranges.clone(),
+        hovered_item: m.hovered_item,
+    };
+    m.history.undo_stack.push(snapshot);
+    m.history.redo_stack.clear();
+}
+
+fn undo(m: &mut Model) {
+    match m.history.undo_stack.pop() {
+        None => set_status(m, "nothing to undo".to_string()),
+        Some(snapshot) => {
+            set_status(m, format!("undid {}", snapshot.description));
+            let redo_snapshot = restore_snapshot(m, snapshot);
+            m.history.redo_stack.push(redo_snapshot);
+        }
+    }
+}
+
+fn redo(m: &mut Model) {
+    match m.history.redo_stack.pop() {
+        None => set_status(m, "nothing to redo".to_string()),
+        Some(snapshot) => {
+            set_status(m, format!("redid {}", snapshot.description));
+            let undo_snapshot = restore_snapshot(m, snapshot);
+            m.history.undo_stack.push(undo_snapshot);
+        }
+    }
+}
+
+fn restore_snapshot(m: &mut Model, snapshot: Snapshot) -> Snapshot {
+    // swap in the snapshot's edit state, and return the replaced state
+    let Snapshot {
+        description,
+        markers,
+        segments,
+        ranges,
+        hovered_item,
+    } = snapshot;
+    let replaced = Snapshot {
+        description: description,
+        markers: std::mem::replace(&mut m.markers, markers),
+        segments: std::mem::replace(&mut m.segments, segments),
+        ranges: std::mem::replace(&mut m.ranges, ranges),
+        hovered_item: m.hovered_item,
+    };
+    m.needs_to_clear = true; // number of range rows may have changed
+
+    // hover the marker from before the edit, and move the playhead back to it like goto_prev_marker().
+    // otherwise, go back to hovering whichever segment we're in
+    match hovered_item.mode {
+        HoverMode::Markers if hovered_item.position < m.markers.len() => {
+            m.hovered_item = hovered_item;
+            let timestamp: Seconds = m.markers[hovered_item.position];
+            m.frame_number = (timestamp * m.VIDEO_METADATA.fps) as u32;
+            seek_to(m, timestamp);
+            m.paused = true;
+        }
+        _ => hover_current_segment(m),
+    }
+    return replaced;
+}
+
+fn hover_current_segment(m: &mut Model) {
+    // after markers change without the playhead moving
+    let cur_timestamp = m.frame_number as f64 * m.VIDEO_METADATA.seconds_per_frame;
+    m.hovered_item = Hovering {
+        mode: HoverMode::Segments,
+        position: m.markers.iter().filter(|t| cur_timestamp > **t).count(),
+    };
+}
+
+fn set_status(m: &mut Model, msg: String) {
+    log!("{}", msg);
+    m.status_msg = Some((msg, std::time::Instant::now()));
+}
+
+fn advance_one_frame(m: &mut Model) {
+    match m.paused {
+        false => (),
+        true => {
+            match m.frame_iterator.skip_some_frames(1) {
+                Some(frame) => {
+                    m.frame = frame;
+                    m.frame_number += 1;
+                    update_if_moved_past_segment(m);
+                }
+                None => set_status(m, "end of video".to_string()),
+            }
+            m.hovered_item.mode = HoverMode::Segments;
+        }
+    }
+}
+
+fn skip_to_percent(m: &mut Model, percent: u32) {
+    // skip to arbitrary point in video. useful to avoid many repeated skips.
+    // low-priority TODO: implement mouse listener to seek by clicking?
+    let timestamp: Seconds = m.VIDEO_METADATA.duration_secs * percent as f64 / 100.0;
+    skip_to_timestamp(m, timestamp);
+}
+
+fn skip_to_timestamp(m: &mut Model, timestamp: Seconds) {
+    let timestamp = timestamp.clamp(0.0, m.VIDEO_METADATA.duration_secs);
+    let frame_number = (timestamp * m.VIDEO_METADATA.fps).floor() as u32;
+
+    let old_frame_number = m.frame_number;
+    m.frame_number = frame_number;
+    seek_to(m, timestamp);
+
+    let moved_forward = m.frame_number > old_frame_number;
+    match moved_forward {
+        true => update_if_moved_past_segment(m),
+        false => update_if_moved_behind_segment(m),
+    };
+    m.hovered_item.mode = HoverMode::Segments;
+}
+
+// --- IPC --- //
+
+fn run_ipc_command(m: &mut Model, request: &ipc::Request) -> Result<Value, String> {
+    // see ipc.rs for the protocol.
+    // edits go through the same undo history as keypresses
+    let name = request.command[0].as_str().unwrap_or("");
+    let secs_arg = || -> Result<Seconds, String> {
+        return request
+            .command
+            .get(1)
+            .and_then(Value::as_f64)
+            .ok_or(format!("{} needs a time in seconds", name));
+    };
+    match name {
+        "seek" => skip_to_timestamp(m, secs_arg()?),
+        "pause" if !m.paused => toggle_paused(m),
+        "play" if m.paused => toggle_paused(m),
+        "pause" | "play" => (),
+        "add-marker" => {
+            // at the current frame, unless given a time
+            let timestamp = match request.command.len() {
+                1 => m.frame_number as Seconds / m.VIDEO_METADATA.fps,
+                _ => secs_arg()?,
+            };
+            add_marker_at(m, timestamp)?;
+        }
+        "remove-marker" => remove_marker_at(m, secs_arg()?)?,
+        "get-position" => {
+            return Ok(Value::object(vec![
+                ("position", Value::Number(position_secs(m))),
+                ("frame", Value::Number(m.frame_number as f64)),
+                ("paused", Value::Bool(m.paused)),
+            ]));
+        }
+        "get-markers" => return Ok(markers_json(m)),
+        "get-metadata" => {
+            return Ok(Value::object(vec![
+                ("path", Value::Str(m.video_path.clone())),
+                ("width", Value::Number(m.VIDEO_METADATA.width_px as f64)),
+                ("height", Value::Number(m.VIDEO_METADATA.height_px as f64)),
+                ("fps", Value::Number(m.VIDEO_METADATA.fps)),
+                ("duration", Value::Number(m.VIDEO_METADATA.duration_secs)),
+            ]));
+        }
+        "subscribe" => {
+            if let Some(ipc) = &m.ipc {
+                ipc.subscribe(request.client_id);
+            }
+        }
+        "export" => plan_export(m)?, // the caller finishes after replying
+        _ => return Err(format!("unknown command {}", request.command[0])),
+    };
+    return Ok(Value::Null);
+}
+
+fn position_secs(m: &Model) -> Seconds {
+    return m.frame_number as Seconds / m.VIDEO_METADATA.fps;
+}
+
+fn markers_json(m: &Model) -> Value {
+    return Value::Array(m.markers.iter().map(|t| Value::Number(*t)).collect());
+}
+
+fn add_marker_at(m: &mut Model, timestamp: Seconds) -> Result<(), String> {
+    let half_frame = m.VIDEO_METADATA.seconds_per_frame / 2.0;
+    if !(0.0..=m.VIDEO_METADATA.duration_secs).contains(&timestamp) {
+        return Err(format!("{} is outside the video", timestamp));
+    }
+    if m.markers.iter().any(|t| (t - timestamp).abs() < half_frame) {
+        return Err(format!(
+            "there's already a marker at {}",
+            format_secs_to_mm_ss(timestamp)
+        ));
+    }
+    record_edit(
+        m,
+        format!("make marker at {}", format_secs_to_mm_ss(timestamp)),
+    );
+    insert_marker(&mut m.markers, &mut m.segments, timestamp);
+    hover_current_segment(m);
+    return Ok(());
+}
+
+fn remove_marker_at(m: &mut Model, timestamp: Seconds) -> Result<(), String> {
+    // the nearest marker within half a frame, since times from clients may be rounded
+    let half_frame = m.VIDEO_METADATA.seconds_per_frame / 2.0;
+    let index = m
+        .markers
+        .iter()
+        .position(|t| (t - timestamp).abs() < half_frame)
+        .ok_or(format!("no marker at {}", format_secs_to_mm_ss(timestamp)))?;
+    record_edit(
+        m,
+        format!("delete marker at {}", format_secs_to_mm_ss(timestamp)),
+    );
+    remove_marker(&mut m.markers, &mut m.segments, index);
+    hover_current_segment(m);
+    return Ok(());
+}
+
+fn push_ipc_events(m: &mut Model) {
+    // subscribers hear about changes, but not every frame of playback.
+    // while playing, the position goes out about once a second,
+    // or right away after seeking backwards
+    if m.ipc.is_none() {
+        return;
+    }
+    let position = position_secs(m);
+    let markers = markers_json(m);
+    let paused = m.paused;
+    if let Some(ipc) = &mut m.ipc {
+        ipc.push("pause", Value::Bool(paused));
+        ipc.push("markers", markers);
+        let last_position = ipc.last_pushed("position").and_then(Value::as_f64);
+        let need_to_push_position = match (paused, last_position) {
+            (_, None) => true,
+            (true, Some(_)) => true, // unless it's the same, see push()
+            (false, Some(last)) => position < last || position - last >= 1.0,
+        };
+        if need_to_push_position {
+            ipc.push("position", Value::Number(position));
+        }
+    }
+}
+
+// --- VIEW --- //
+
+fn format_secs_to_mm_ss(seconds: Seconds) -> String {
+    let minutes = (seconds / 60.0).floor();
+    let remaining_secs = (seconds - (minutes * 60.0)).floor();
+    return format!("{}:{:0>2}", minutes, remaining_secs);
+}
+
+fn view(m: &Model, outbuf: &mut impl std::io::Write) {
+    // --- empty cases and setup --- //
+
+    if m.needs_to_clear {
+        queue!(outbuf, terminal::Clear(terminal::ClearType::All));
+    }
+
+    queue!(outbuf, MoveTo(0, 0),);
+    // a lot of ugly syntax just to make an empty placeholder frame
+    let mut lines: Box<dyn std::iter::Iterator<Item = &str>> = match m.frame.is_empty() {
+        false => Box::new(m.frame.split("\n")),
+        true => Box::new(std::iter::repeat("").take(m.frame_iterator.output_rows as usize)),
+    };
+
+    // --- draw colorful video frame and overlaid labels --- //
+    //
+    // TODO: fix flickering labels overlayed on video.
+    // Currently, terminal emulator prints colorful video and then backtracks to print text.
+    // need to parse line of video output, which looks like
+    // 38;2;r;g;b;48;2;r;g;b;  38;2;r;g;b;48;2;r;g;b; ... [0m
+    //
+    // also consider using new-age control character for synchornized terminal render update,
+    // created by ghostty/zig people
+
+    let first_line = lines.next().unwrap();
+    queue!(
+        outbuf,
+        Print(" "),
+        Print(first_line),
+        // hardcoded template width, assuming double digit fps
+        MoveLeft(8),
+        Print(match m.recent_fps {
+            Some(fps) => format!(" fps: {:2.0}", fps),
+            None => " fps:   ".to_string(),
+        }),
+        MoveToNextLine(1),
+    );
+    for line in lines {
+        queue!(outbuf, Print(" "), Print(line), MoveToNextLine(1),);
+    }
+    queue!(
+        outbuf,
+        MoveToPreviousLine(1),
+        Print(" "),
+        Print(format!(
+            "{} / {} ",
+            format_secs_to_mm_ss(m.frame_number as f64 / m.VIDEO_METADATA.fps),
+            format_secs_to_mm_ss(m.VIDEO_METADATA.duration_secs)
+        )),
+        MoveToColumn(m.frame_iterator.output_cols - 1),
+        // Print(match m.paused {
+        Print(match m.paused {
+            true => " ||",
+            false => " >>",
+            // TODO: implment video speeds
+            // true => "paused || x2 / x1 / x0.5",
+            // false => "playing >> x2 / x1 / x0.5",
+            // TODO: implement loading widget for async UI moments
+            // buffering => "buffering 6-frame loading cycle of braille ⠆⠃⠉⠘⠰⠤
+        }),
+        MoveToNextLine(1),
+    );
+
+    // --- draw playerbar and stats --- //
+    //
+    //  1:04.567 / 1:23                 x1  >>
+    // ┏-----------┳--v--┳━━━━━━━━━━━━━━━━━━━━┓
+    // ┗-----------┻-----┻━━━━━━━━━━━━━━━━━━━━┛
+    //  segment 2 of 3
+    //
+    //  0:56.789 / 1.23         x2 ||
+    // ┌-----------v-----┬───────────┐
+    // └-----------┴-----┴───────────┘
+    //  marker 1 of 2
+
+    let percent_complete =
+        (m.frame_number as f64 / m.VIDEO_METADATA.duration_secs / m.VIDEO_METADATA.fps) * 100.0;
+    let playerhead_position =
+        (percent_complete * m.frame_iterator.output_cols as f64 / 100.0) as Columns;
+
+    queue!(
+        outbuf,
+        Print(format!(
+            "┌{}┐",
+            "─".repeat(m.frame_iterator.output_cols as usize)
+        )),
+        MoveToNextLine(1),
+        Print(format!(
+            "└{}┘",
+            "─".repeat(m.frame_iterator.output_cols as usize)
+        )),
+        MoveToPreviousLine(1)
+    );
+    // dashed lines for discarded segments
+    //
+    //  ┌─────┬╌╌╌╌╌┬──────┐
+    //  └─────┴╌╌╌╌╌┴──────┘
+    let to_column = |timestamp: &Seconds| {
+        (m.frame_iterator.output_cols as f64 * (timestamp / m.VIDEO_METADATA.duration_secs))
+            as Columns
+    };
+    for (i, segment) in m.segments.iter().enumerate() {
+        if segment.kept {
+            continue;
+        }
+        let start_column = match i {
+            0 => 1,
+            _ => to_column(&m.markers[i - 1]) + 1,
+        };
+        let end_column = match m.markers.get(i) {
+            Some(timestamp) => to_column(timestamp),
+            None => m.frame_iterator.output_cols + 1,
+        };
+        let dashes = "╌".repeat(end_column.saturating_sub(start_column) as usize);
+        queue!(
+            outbuf,
+            MoveToColumn(start_column),
+            Print(&dashes),
+            MoveToNextLine(1),
+            MoveToColumn(start_column),
+            Print(&dashes),
+            MoveToPreviousLine(1)
+        );
+    }
+    for timestamp in &m.markers {
+        let position = (m.frame_iterator.output_cols as f64
+            * (timestamp / m.VIDEO_METADATA.duration_secs)) as Columns;
+        queue!(
+            outbuf,
+            MoveToColumn(position),
+            Print("┬"),
+            MoveToNextLine(1),
+            MoveToColumn(position),
+            Print("┴"),
+            MoveToPreviousLine(1)
+        );
+    }
+    queue!(
+        outbuf,
+        MoveToColumn(playerhead_position),
+        Print("v"),
+        MoveToNextLine(2),
+    );
+
+    // --- draw in/out ranges, stacking overlaps onto separate rows --- //
+    //
+    // ┌────────────────────────────────────┐
+    // └────────────────────────────────────┘
+    //    ┝━━━━━━━━━━┥    ┝━━━━━━━━┥
+    //          ┝━━━━━━━━━━━━┥
+    //                          ┝┄┄┄┄┄   <- pending in point
+
+    let blank_row = " ".repeat(m.frame_iterator.output_cols as usize + 2);
+    let lanes = assign_lanes(&m.ranges);
+    let num_lanes = lanes.iter().max().map_or(0, |lane| lane + 1);
+    for lane in 0..num_lanes {
+        queue!(outbuf, MoveToColumn(0), Print(&blank_row));
+        for (range, _) in m.ranges.iter().zip(&lanes).filter(|(_, l)| **l == lane) {
+            let start_column = to_column(&range.start);
+            let end_column = std::cmp::max(to_column(&range.end), start_column + 1);
+            queue!(
+                outbuf,
+                MoveToColumn(start_column),
+                Print("┝"),
+                Print("━".repeat((end_column - start_column - 1) as usize)),
+                Print("┥"),
+            );
+        }
+        queue!(outbuf, MoveToNextLine(1));
+    }
+    if let Some(in_timestamp) = m.pending_in {
+        let in_column = to_column(&in_timestamp);
+        let left = std::cmp::min(in_column, playerhead_position);
+        let right = std::cmp::max(in_column, playerhead_position);
+        queue!(
+            outbuf,
+            MoveToColumn(0),
+            Print(&blank_row),
+            MoveToColumn(left),
+            Print("┄".repeat((right - left + 1) as usize)),
+            MoveToColumn(in_column),
+            Print("┝"),
+            MoveToNextLine(1),
+        );
+    }
+
+    // --- draw helper text and controls --- //
+    //
+    //  segment 2 of 3                   help ?
+    //
+    //     m = make marker
+    //   J/L = prev/next marker
+    //     s = keep segment
+    // space = pause
+    //   ←/→ = back/forwards 5 secs
+    //   j/l = back/forwards 15 secs
+    //   0-9 = skip to 0%, 10%, etc
+    //     . = advance one frame
+    //     q = finish, making 1 segment
+    //
+    // TODO: consider the lovely bottom-help text from bubbletea,
+    // like in this demo: https://github.com/charmbracelet/wishlist
+
+    let num_markers = m.markers.len();
+    let num_segments = num_markers + 1;
+    let num_kept_segments = m.segments.iter().filter(|segment| segment.kept).count();
+
+    let hovered_text = match m.hovered_item.mode {
+        HoverMode::Segments => format!(
+            " segment {} of {}{}{}",
+            m.hovered_item.position + 1,
+            num_segments,
+            match m.segments[m.hovered_item.position].label.as_str() {
+                "" => "".to_string(),
+                label => format!(" \"{}\"", label),
+            },
+            match m.segments[m.hovered_item.position].kept {
+                true => "",
+                false => " (discarded)",
+            }
+        ),
+        // HoverMode::Segments => format!("     {} segments", num_segments),
+        HoverMode::Markers => format!(
+            " marker {} of {} ",
+            m.hovered_item.position + 1,
+            num_markers
+        ),
+    };
+    queue!(
+        outbuf,
+        // padded up to "help?", to cover longer text from the previous frame,
+        // like " (discarded)" after deleting a marker
+        Print(format!(
+            "{:width$}",
+            hovered_text,
+            width = (m.frame_iterator.output_cols as usize).saturating_sub(4)
+        )),
+        MoveToColumn(m.frame_iterator.output_cols - 3),
+        // Print("help".dark_grey().bold()),
+        Print("h"),
+        Print("elp"),
+        Print("?\n"),
+        MoveToColumn(1),
+    );
+
+    // status line: label prompt, else brief status msg, else any ffmpeg error as a red banner
+    let decoder_error = m.frame_iterator.last_decoder_error();
+    let (status, color) = match (&m.label_prompt, &m.status_msg, decoder_error) {
+        (Some(text), _, _) => (format!("label: {}▏", text), Color::Reset),
+        (None, Some((msg, instant)), _) if instant.elapsed() < STATUS_MSG_DURATION => {
+            (msg.to_string(), Color::Reset)
+        }
+        (None, _, Some(error)) => (format!("ffmpeg: {}", error), Color::Red),
+        _ => ("".to_string(), Color::Reset),
+    };
+    queue!(
+        outbuf,
+        SetForegroundColor(color),
+        Print(format!(
+            "{:width$}\n",
+            status,
+            width = m.frame_iterator.output_cols as usize
+        )),
+        ResetColor,
+        MoveToColumn(1),
+    );
+
+    // video details, like:
+    //
+    //  container ..... QuickTime / MOV
+    //  codec ......... h264 (High)
+    //  ...
+    if m.show_info {
+        let lines = match &m.info {
+            Some(Ok(info)) => info.describe(),
+            Some(Err(e)) => vec![format!("failed to probe: {}", e)],
+            None => vec!["probing...".to_string()],
+        };
+        for line in lines {
+            queue!(outbuf, Print(format!(" {}\n", line)), MoveToColumn(1));
+        }
+        queue!(outbuf, Print("\n"), MoveToColumn(1));
+    }
+
+    if m.hide_controls {
+        return;
+    }
+
+    // each line is like `  j/l = back/forwards 15 secs`, using the keys from the keymap,
+    // and skipped if its actions are unbound
+    let control = |actions: &[Action], description: &str| -> Option<String> {
+        return m
+            .keymap
+            .label(actions, true)
+            .map(|label| format!("{:>6} = {:<24}", label, description));
+    };
+    let is_marker_mode = matches!(m.hovered_item.mode, HoverMode::Markers);
+    let can_nav_markers = match (&m.hovered_item.mode, m.markers.len()) {
+        (HoverMode::Segments, 0) => false, // no markers
+        (HoverMode::Markers, 1) => false,  // cannot nav left or right
+        _ => true,
+    };
+    let lines = [
+        match m.hovered_item.mode {
+            HoverMode::Segments => control(&[Action::MakeMarker], "make marker"),
+            HoverMode::Markers => control(&[Action::RemoveMarker], "remove marker"),
+        },
+        match is_marker_mode {
+            true => control(
+                &[Action::NudgeBackFrame, Action::NudgeForwardFrame],
+                "nudge marker 1 frame",
+            ),
+            false => None,
+        },
+        match is_marker_mode {
+            true => control(
+                &[Action::NudgeBackSec, Action::NudgeForwardSec],
+                "nudge marker 1 sec",
+            ),
+            false => None,
+        },
+        match m.hovered_item.mode {
+            HoverMode::Segments => match m.segments[m.hovered_item.position].kept {
+                true => control(&[Action::ToggleKept], "discard segment"),
+                false => control(&[Action::ToggleKept], "keep segment"),
+            },
+            HoverMode::Markers => None,
+        },
+        match m.hovered_item.mode {
+            HoverMode::Segments => control(&[Action::LabelSegment], "label segment"),
+            HoverMode::Markers => None,
+        },
+        control(
+            &[Action::SetInPoint, Action::SetOutPoint],
+            "set in/out point",
+        ),
+        match m.ranges.len() {
+            0 => None,
+            _ => control(&[Action::DeleteRange], "delete range"),
+        },
+        match can_nav_markers {
+            true => control(
+                &[Action::PrevMarker, Action::NextMarker],
+                "prev/next marker",
+            ),
+            false => None,
+        },
+        match m.paused {
+            true => control(&[Action::TogglePause], "unpause"),
+            false => control(&[Action::TogglePause], "pause"),
+        },
+        control(
+            &[Action::SeekBack15s, Action::SeekForward15s],
+            "back/forwards 15 secs",
+        ),
+        control(
+            &[Action::SeekBack5s, Action::SeekForward5s],
+            "back/forwards 5 secs",
+        ),
+        control(
+            &[Action::SeekToPercent(0), Action::SeekToPercent(90)],
+            "skip to 0%, 10%, etc",
+        ),
+        control(&[Action::AdvanceFrame], "advance one frame"),
+        control(&[Action::SaveFrame], "save frame as png"),
+        control(&[Action::Undo, Action::Redo], "undo/redo"),
+        match m.show_info {
+            true => control(&[Action::ToggleInfo], "hide video info"),
+            false => control(&[Action::ToggleInfo], "show video info"),
+        },
+        control(&[Action::ToggleHelp], "hide controls"),
+        // TODO: enable / disable dry run
+        match num_segments {
+            1 => control(&[Action::Quit], "quit"),
+            _ => control(
+                &[Action::Quit],
+                &format!("quit and cut into {} segments", num_kept_segments),
+            ), // and print {} recipes
+        },
+    ];
+    for line in lines.iter().flatten() {
+        queue!(outbuf, Print(format!("{}\n", line)), MoveToColumn(1));
+    }
+    queue!(outbuf, Print("                                        "));
+}
+
+// --- APP START --- //
+
+fn parse_cli_args(
+    pargs: &mut pico_args::Arguments,
+    defaults: ConfigDefaults,
+    needs_video: bool, // --print-config doesn't need one
+) -> Result<CliArgs, String> {
+    let args = CliArgs {
+        video_filepath: match needs_video {
+            true => pargs
+                .free_from_str::<std::path::PathBuf>()
+                // Note: positional args must be listed first, like `vic video.mp4 -w 20`
+                // because pargs always assumes arg[1] is a valid positional arg.
+                // This is a limitation of the pargs library.
+                // Commands like `vic -w 20 video.mp4` will fail, but not on this error.
+                .map_err(|e| {
+                    "failed to parse <filepath>. is the first cli argument the path to a video?\nuse the format `vic [filepath] [options]`"
+                })?
+                .display()
+                .to_string(),
+            false => "".to_string(),
+        },
+        max_width: pargs
+            .opt_value_from_fn("-w", Columns::from_str)
+            .map_err(|e| "failed to parse -w")?
+            .or(defaults.max_width)
+            .unwrap_or(40),
+        // either a bare --dry-run, or --dry-run=<format>
+        dry_run: match pargs.contains("--dry-run") {
+            true => Some(recipe::DryRunFormat::Plain),
+            false => pargs
+                .opt_value_from_str::<_, recipe::DryRunFormat>("--dry-run")
+                .map_err(|e| format!("failed to parse --dry-run. {}", e))?,
+        },
+        print_format: pargs
+            .opt_value_from_str::<_, report::PrintFormat>("--print")
+            .map_err(|e| format!("failed to parse --print. {}", e))?,
+        output_template: pargs
+            .opt_value_from_str::<_, String>("--output-template")
+            .map_err(|e| "failed to parse --output-template")?
+            .or(defaults.output_template),
+        output_dir: pargs
+            .opt_value_from_str::<_, std::path::PathBuf>("--output-dir")
+            .map_err(|e| "failed to parse --output-dir. did you include a directory?")?,
+        preset: pargs
+            .opt_value_from_str::<_, export::Preset>("--preset")
+            .map_err(|e| format!("failed to parse --preset. {}", e))?
+            .or(defaults.preset),
+        ffmpeg_args: pargs
+            .opt_value_from_fn("--ffmpeg-args", export::split_ffmpeg_args)
+            .map_err(|e| format!("failed to parse --ffmpeg-args. {}", e))?
+            .unwrap_or_default(),
+        export_mode: pargs
+            .opt_value_from_str::<_, export::ExportMode>("--export")
+            .map_err(|e| format!("failed to parse --export. {}", e))?
+            .unwrap_or(export::ExportMode::Video),
+        export_width: pargs
+            .opt_value_from_str("--export-width")
+            .map_err(|e| "failed to parse --export-width")?
+            .unwrap_or(480),
+        export_fps: pargs
+            .opt_value_from_str("--export-fps")
+            .map_err(|e| "failed to parse --export-fps")?
+            .unwrap_or(15),
+        export_every: pargs
+            .opt_value_from_str("--export-every")
+            .map_err(|e| "failed to parse --export-every")?
+            .unwrap_or(1),
+        audio_format: pargs
+            .opt_value_from_str::<_, export::AudioFormat>("--audio-format")
+            .map_err(|e| format!("failed to parse --audio-format. {}", e))?
+            .unwrap_or(export::AudioFormat::Flac),
+        concat_audio: pargs.contains("--concat-audio"),
+        jobs: pargs
+            .opt_value_from_str("--jobs")
+            .map_err(|e| "failed to parse --jobs")?
+            .unwrap_or(1),
+        log_filepath: pargs
+            // let user decide if and where to log
+            //
+            // early versions were hardcoded as /tmp/vic_log
+            // consider also $LOCALAPPDATA/vic/log for windows,
+            // and maybe /var/vic_log, $HOME/.vic/log, or that $XDG_HOME thing for linux
+            .opt_value_from_str::<_, std::path::PathBuf>("--log")
+            .map_err(|e| "failed to parse --log. did you include a filepath?")?
+            .or(defaults.log_filepath),
+        // .map(|opt_pathbuf| opt_pathbuf.display().to_string()), // map from Option<PathBuf> to Option<&str>
+        record_keys: pargs
+            .opt_value_from_str::<_, std::path::PathBuf>("--record-keys")
+            .map_err(|e| "failed to parse --record-keys. did you include a filepath?")?,
+        replay_keys: pargs
+            .opt_value_from_str::<_, std::path::PathBuf>("--replay-keys")
+            .map_err(|e| "failed to parse --replay-keys. did you include a filepath?")?,
+        ipc_path: pargs
+            .opt_value_from_str::<_, std::path::PathBuf>("--ipc")
+            .map_err(|e| "failed to parse --ipc. did you include a socket path?")?,
+        looping: pargs.contains("--loop"),
+        render_mode: pargs
+            .opt_value_from_str::<_, RenderMode>("--render")
+            .map_err(|e| format!("failed to parse --render. {}", e))?
+            .or(defaults.render_mode)
+            .unwrap_or(RenderMode::Truecolor),
+        // --blocky is the older way to say --symbols blocky
+        symbols: match pargs.contains("--blocky") {
+            true => SymbolSet::Blocky,
+            false => pargs
+                .opt_value_from_str::<_, SymbolSet>("--symbols")
+                .map_err(|e| format!("failed to parse --symbols. {}", e))?
+                .or(defaults.symbols)
+                .unwrap_or(SymbolSet::Default),
+        },
+        muted: pargs.contains("--muted"),
+    };
+    return Ok(args);
+}
+
+fn reject_leftover_args(pargs: pico_args::Arguments) -> Result<(), VicError> {
+    // a mistyped option, or a second video, shouldn't be silently ignored
+    let leftover_args = pargs.finish();
+    if leftover_args.is_empty() {
+        return Ok(());
+    }
+    return Err(VicError::BadCliArg(format!(
+        "unexpected arguments: {}\nsee `vic --help` for the options",
+        leftover_args
+            .iter()
+            .map(|arg| arg.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join(" ")
+    )));
+}
+
+fn effective_config_text(
+    args: &CliArgs,
+    keymap: &Keymap,
+    config_file: &config::ConfigFile,
+) -> String {
+    // for --print-config, in the same format as the config file,
+    // so it can be saved as a starting point
+    let mut text = format!(
+        "# built-in defaults, then {}, then command-line args\n\n",
+        match &config_file.path {
+            Some(path) => path.display().to_string(),
+            None => "no config file".to_string(),
+        }
+    );
+    text.push_str(&format!("width = {}\n", args.max_width));
+    text.push_str(&format!(
+        "render = {}\n",
+        json::quote(args.render_mode.name())
+    ));
+    text.push_str(&format!("symbols = {}\n", json::quote(args.symbols.name())));
+    let optional_settings = [
+        (
+            "preset",
+            args.preset.map(|preset| preset.name().to_string()),
+        ),
+        ("output-template", args.output_template.clone()),
+        (
+            "log",
+            args.log_filepath
+                .as_ref()
+                .map(|path| path.display().to_string()),
+        ),
+    ];
+    for (key, value) in optional_settings {
+        match value {
+            Some(value) => text.push_str(&format!("{} = {}\n", key, json::quote(&value))),
+            None => text.push_str(&format!("# {} is not set\n", key)),
+        }
+    }
+    text.push('\n');
+    text.push_str(&keymap.config_text());
+    return text;
+}
+
+fn new_model(
+    args: CliArgs,
+    keymap: Keymap,
+    source: Box<dyn FrameSource>,
+    terminal_size: (Columns, Rows),
+) -> Model {
+    // separate from init(), so tests can make a model without a terminal or ffmpeg
+    let (cols, rows) = terminal_size;
+    let start = std::time::Instant::now();
+    let video_metadata = source.metadata().clone();
+    let fps = video_metadata.fps;
+
+    let aspect_ratio = video_metadata.width_px as f64 / video_metadata.height_px as f64;
+    let output_cols = std::cmp::min(cols - 2, args.max_width - 2) as Columns;
+    let output_rows = (output_cols as f64 / aspect_ratio / 2.0).ceil() as Rows;
+    log!("{:?} {:?} {:?}", aspect_ratio, output_cols, output_rows);
+
+    let frame_iterator = FrameIterator::new(
+        source,
+        output_cols,
+        output_rows,
+        args.symbols,
+        args.render_mode,
+    );
+
+    return Model {
+        paused: false,
+        looping: args.looping,
+        frame_number: 0,
+        speed: 1.0,
+        markers: Vec::<Seconds>::new(),
+        segments: vec![Segment {
+            kept: true,
+            label: "".to_string(),
+        }],
+        ranges: Vec::<ClipRange>::new(),
+        pending_in: None,
+        history: History::default(),
+        hovered_item: Hovering {
+            mode: HoverMode::Segments,
+            position: 0,
+        },
+        terminal_cols: cols,
+        terminal_rows: rows,
+        video_path: args.video_filepath,
+        VIDEO_METADATA: video_metadata,
+        frame_iterator: frame_iterator,
+        hide_controls: true,
+        show_info: false,
+        info: None,
+        is_probing: false,
+        keymap: keymap,
+        frame: "".to_string(),
+        needs_to_clear: false,
+        status_msg: None,
+        label_prompt: None,
+        still_frames: Vec::<Seconds>::new(),
+        prev_instant: start,
+        last_fps_check: start,
+        recent_fps: None,
+        start: start,
+        replay_time: None,
+        key_recording: None,
+        ipc: None,
+        events: None,
+        accumulated_time: 0.0,
+        export_options: export::ExportOptions {
+            dry_run: args.dry_run.is_some(),
+            dry_run_format: args.dry_run.unwrap_or(recipe::DryRunFormat::Plain),
+            output_template: args.output_template,
+            output_dir: args.output_dir,
+            preset: args.preset,
+            ffmpeg_args: args.ffmpeg_args,
+            mode: args.export_mode,
+            width_px: args.export_width,
+            fps: args.export_fps,
+            every_nth_frame: args.export_every,
+            audio_format: args.audio_format,
+            concat_audio: args.concat_audio,
+            num_jobs: args.jobs,
+        },
+        jobs: vec![],
+        export_error: None,
+        print_format: args.print_format,
+    };
+}
+
+fn init(events: AppEvents<AppEvent>) -> Result<Model, VicError> {
+    // Receive command-line args.
+    // https://github.com/RazrFalcon/pico-args/blob/master/examples/app.rs
+    let mut pargs = pico_args::Arguments::from_env();
+
+    // `vic probe <filepath> [--json]` prints video details instead of starting the TUI.
+    // checked before anything else, since the usual first arg is a video.
+    // a video that's actually named probe still opens with `vic ./probe`
+    if std::env::args().nth(1).as_deref() == Some("probe") {
+        pargs.subcommand();
+        let as_json = pargs.contains("--json");
+        let video_filepath = pargs
+            .free_from_str::<std::path::PathBuf>()
+            .map_err(|e| {
+                VicError::BadCliArg(
+                    "failed to parse <filepath>\nuse the format `vic probe <filepath> [--json]`"
+                        .to_string(),
+                )
+            })?
+            .display()
+            .to_string();
+        reject_leftover_args(pargs)?;
+        let info = probe::get_ffprobe_extended_metadata(&video_filepath)?;
+        match as_json {
+            true => println!("{}", info.to_json().pretty()),
+            false => println!("{}", info.describe().join("\n")),
+        }
+        std::process::exit(0);
+    }
+
+    if pargs.contains(["-v", "--version"]) {
+        print!("{}\n", env!("CARGO_PKG_VERSION"));
+        std::process::exit(0);
+    }
+
+    // the config file and keymap come before --help, which lists the active keys
+    let wants_help = pargs.contains(["-h", "--help"]);
+    let config_filepath = pargs
+        .opt_value_from_str::<_, std::path::PathBuf>("--config")
+        .map_err(|e| {
+            VicError::BadCliArg("failed to parse --config. did you include a filepath?".to_string())
+        })?;
+    let keymap_preset = pargs
+        .opt_value_from_str::<_, String>("--keymap")
+        .map_err(|e| VicError::BadCliArg("failed to parse --keymap".to_string()))?;
+    let loaded = match config_filepath {
+        Some(path) => config::load(path),
+        None => config::load_default(),
+    }
+    .and_then(|config_file| {
+        let keymap = keymap::load(&config_file, keymap_preset.as_deref())?;
+        return Ok((config_file, keymap));
+    });
+    let (config_file, keymap) = match (loaded, wants_help) {
+        (Ok(loaded), _) => loaded,
+        // a typo in the config file shouldn't hide the help that explains it
+        (Err(msg), true) => {
+            eprintln!("Warning: {}\nshowing the default keys instead\n", msg);
+            (
+                config::ConfigFile::default(),
+                Keymap::preset("default").unwrap(),
+            )
+        }
+        (Err(msg), false) => return Err(VicError::BadConfig(msg)),
+    };
+
+    let HELP_MSG: String = format!(
+        "
+ vic {} - cut videos in the terminal
+
+ ________
+ EXAMPLES
+
+   vic video.mp4
+   vic video.mp4 -w=9999
+   vic http://example.com/video.avi -w 20
+   vic video.webm -w 80 --dry-run
+   vic video.mp4 --dry-run=sh > cut.sh
+   vic video.mp4 --print json > edits.json
+   vic video.mp4 --log log.txt
+   vic video.mp4 --record-keys bug.keys
+   vic video.mp4 --replay-keys bug.keys --dry-run
+   vic video.mp4 --ipc /tmp/vic.sock
+   vic video.mp4 --config ~/vic-export.toml
+   vic --print-config -w 80 > ~/.config/vic/config.toml
+   vic probe video.mp4 --json
+   vic video.mp4 --preset web-h264 --ffmpeg-args '-an'
+   vic video.mp4 --preset archive-ffv1 --jobs 4
+   vic video.mp4 --export gif --export-width 320 --export-fps 10
+   vic video.mp4 --export frames --export-every 24
+   vic interview.mp4 --export audio --audio-format mp3 --concat-audio
+   vic video.mp4 --output-template '{{stem}}_{{index:02}}_{{label}}.{{ext}}' --output-dir clips/
+
+ _____
+ USAGE
+
+   vic <filepath> [-w <int, default 40>]
+                  [--dry-run[=plain|sh|json|bat]]
+                  [--print <markers|segments|json>]
+                  [--output-template <template>]
+                  [--output-dir <dirpath>]
+                  [--preset <name>]
+                  [--ffmpeg-args <args>]
+                  [--export <video|gif|webp|frames|audio>]
+                  [--export-width <int, default 480>]
+                  [--export-fps <int, default 15>]
+                  [--export-every <int, default 1>]
+                  [--audio-format <wav|flac|mp3|opus>]
+                  [--concat-audio]
+                  [--jobs <int, default 1>]
+                  [--loop]
+                  [--keymap <default|vim|mpv>]
+                  [--render <truecolor|256|16>]
+                  [--symbols <default|blocky|all>]
+                  [--log <filepath>]
+                  [--record-keys <filepath>]
+                  [--replay-keys <filepath>]
+                  [--ipc <socket-path>]
+                  [--config <filepath>]
+                  [--help|--version]
+
+   vic --print-config [options]
+   vic probe <filepath> [--json]
+ _______
+ OPTIONS
+
+   -w <int>          Max output width, in columns.
+                     Use -w 9999 for fullscreen.
+                     Defaults to 40.
+
+   --dry-run[=<format>]
+                     Instead of running ffmpeg on finish,
+                     just print the commands to stdout.
+                     plain .. one command per line
+                     sh ..... runnable POSIX shell script
+                     json ... array of argv arrays
+                     bat .... runnable windows batch file
+                     Defaults to plain.
+
+   --print <format>  On finish, also print the edits to stdout.
+                     markers ... one marker time per line, in seconds
+                     segments .. start, end, kept or discarded,
+                                 and label, tab-separated
+                     json ...... markers, segments, and ranges,
+                                 with times and output paths
+
+   --output-template <template>
+                     Filename for each output video.
+                     Placeholders: {{stem}} {{ext}} {{index}} {{label}}
+                     {{start_hms}} {{end_hms}}, and zero-padding
+                     like {{index:02}}. Unsafe filename characters
+                     in {{stem}} and {{label}} become _.
+                     Defaults to {{stem}}_{{index}}.{{ext}} for segments
+                     and {{stem}}_{{label}}.{{ext}} for ranges.
+
+   --output-dir <path>
+                     Where to write output videos.
+                     Defaults to the same directory as the input.
+
+   --preset <name>   How to re-encode each output video.
+                     web-h264 ....... h264/aac .mp4, plays anywhere
+                     archive-ffv1 ... lossless ffv1/flac .mkv
+                     small-hevc ..... h265/aac .mp4, slow but small
+                     prores-proxy ... prores .mov, for other editors
+                     same-as-source . no re-encoding, fast,
+                                      but cuts snap to keyframes
+                     Defaults to ffmpeg's choice for the container.
+
+   --ffmpeg-args <args>
+                     Extra ffmpeg args for each output video,
+                     after any preset args. For example:
+                     --ffmpeg-args '-an -vf \"scale=640:-2\"'
+
+   --export <mode>   What to make from each kept segment or range.
+                     video ... cut video files, using any --preset
+                     gif ..... animated gif, with an optimized palette
+                     webp .... animated webp
+                     frames .. png image sequence
+                     audio ... audio only, see --audio-format
+                     Defaults to video.
+
+   --export-width <int>
+                     Width in pixels of animated gifs/webps.
+                     Defaults to 480.
+
+   --export-fps <int>
+                     Framerate of animated gifs/webps.
+                     Defaults to 15.
+
+   --export-every <int>
+                     Only save every nth frame of image sequences,
+                     like 24 for one frame per second of 24fps video.
+                     Defaults to 1.
+
+   --audio-format <format>
+                     wav, flac, mp3, or opus, for --export audio.
+                     Defaults to flac.
+
+   --concat-audio    Join the audio of all kept segments and ranges
+                     into one file, like video_audio.flac,
+                     instead of one file each.
+
+   --jobs <int>      How many outputs to make at once.
+                     Defaults to 1.
+
+   --loop            Restart playback at the end of the video,
+                     instead of pausing.
+
+   --keymap <preset> Which keys to use, before any rebinding
+                     in the config file. See CONFIG.
+                     default .. the keys listed below
+                     vim ...... h/l seek, b/w seek further,
+                                B/W goto prev/next marker
+                     mpv ...... arrow keys seek, pgup/pgdn goto
+                                markers, s saves frames, k keeps
+                     Defaults to default.
+
+   --render <mode>   How many colors to draw with.
+                     truecolor, or 256 or 16 for older terminals.
+                     Defaults to truecolor.
+
+   --symbols <set>   Which characters to draw with.
+                     default .. blocks, shapes, and borders
+                     blocky ... just half blocks, more pixelated
+                     all ...... everything, prettiest but slowest
+                     Defaults to default.
+
+   --log <path>      Write logs to this file.
+
+   --record-keys <path>
+                     Save every keypress and resize to this file,
+                     with its time since start. Handy for bug reports.
+
+   --replay-keys <path>
+                     Replay keys from --record-keys before reading
+                     the keyboard. Replays go the same way every time.
+
+   --ipc <path>      Listen on this unix socket for json commands,
+                     one per line, like {{\"command\": [\"seek\", 12.5]}}.
+                     Commands: seek, pause, play, add-marker,
+                     remove-marker, get-position, get-markers,
+                     get-metadata, subscribe, export.
+
+   --config <path>   Read settings from this file,
+                     instead of the usual config file. See CONFIG.
+
+   --print-config    Print the settings in effect, from the config
+                     file and any other options, as a config file.
+
+   probe <filepath> [--json]
+                     Print details about a video, like its codec,
+                     bitrate, rotation, audio and subtitle streams,
+                     and keyframe interval, then exit.
+                     Also shown in the info panel, I by default.
+
+ ________
+ CONTROLS
+
+{}
+ ______
+ CONFIG
+
+   Settings are read from {}
+   or from --config, and options on the command line override them.
+   For example:
+
+     width = 80                  # like -w
+     render = \"truecolor\"        # like --render
+     symbols = \"blocky\"          # like --symbols
+     preset = \"web-h264\"         # like --preset
+     output-template = \"{{stem}}_{{index:02}}.{{ext}}\"
+     log = \"/tmp/vic_log\"        # like --log
+
+   Keys are rebound by action name, in the [keys] section:
+
+     [keys]
+     preset = \"vim\"
+     make-marker = \"m\"
+     play-pause = [\"space\", \"p\"]
+     redo = \"ctrl+r\"
+     save-frame = []
+
+   Actions:
+
+{}
+ _____
+ NOTES
+
+   vic accepts video filepaths that ffmpeg accepts, including URLs.
+   vic fails if the video has no known duration,
+   which may occur in corrupted or incomplete video files.
+   vic needs at least 14 columns.
+
+   exit codes:
+     1 .. an export failed
+     2 .. bad command-line argument or config file
+     3 .. ffprobe not found
+     4 .. ffmpeg not found
+     5 .. no usable video stream
+     6 .. failed to read the video
+     7 .. other I/O error
+
+   source: https://github.com/wong-justin/vic
+
+",
+        // more examples: ffmpeg -i bigvideo.mp4 -vf scale="iw/4:ih/4" smallvideo.mp4 \
+        //   && vic small_video.mp4 --dry-run
+        env!("CARGO_PKG_VERSION"),
+        keymap::help_text(&keymap),
+        config::default_path()
+            .map(|path| path.display().to_string())
+            .unwrap_or("$XDG_CONFIG_HOME/vic/config.toml".to_string()),
+        keymap::action_names_text(),
+    );
+
+    if wants_help {
+        print!("{}", HELP_MSG);
+        std::process::exit(0);
+    }
+    let print_config = pargs.contains("--print-config");
+    let defaults = read_config_defaults(&config_file).map_err(VicError::BadConfig)?;
+    let args = parse_cli_args(&mut pargs, defaults, !print_config).map_err(VicError::BadCliArg)?;
+    reject_leftover_args(pargs)?;
+    if print_config {
+        print!("{}", effective_config_text(&args, &keymap, &config_file));
+        std::process::exit(0);
+    }
+
+    // println!("{:?}", args);
+    // std::process::exit(0);
+
+    Logs::init(args.log_filepath.clone());
+    log!(
+        "\n--- new session, version {} ---",
+        env!("CARGO_PKG_VERSION")
+    );
+    log!("{:?}", args);
+
+    // fail early on bad export options, rather than after a whole editing session
+    if let Some(template) = &args.output_template {
+        export::render_template(template, "stem", "ext", 0, "label", 0.0, 1.0)
+            .map_err(VicError::BadCliArg)?;
+    }
+    if args.export_every == 0 || args.export_fps == 0 || args.export_width == 0 || args.jobs == 0 {
+        return Err(VicError::BadCliArg(
+            "--export-every, --export-fps, --export-width, and --jobs must be at least 1"
+                .to_string(),
+        ));
+    }
+    if args.print_format.is_some() && args.dry_run.is_some() {
+        return Err(VicError::BadCliArg(
+            "--print and --dry-run both write to stdout, so use one at a time".to_string(),
+        ));
+    }
+    if args.concat_audio && args.export_mode != export::ExportMode::Audio {
+        return Err(VicError::BadCliArg(
+            "--concat-audio only works with --export audio".to_string(),
+        ));
+    }
+    if let Some(dir) = &args.output_dir {
+        if !dir.is_dir() {
+            return Err(VicError::BadCliArg(format!(
+                "--output-dir {} is not a directory",
+                dir.display()
+            )));
+        }
+    }
+
+    // Init app state.
+    let (cols, rows): (Columns, Rows) = terminal::size()?;
+
+    // redraw when the decoder reports an error, even while paused.
+    // the mutex is only so the callback can be shared between decoder threads
+    let source = FfmpegSource::open(&args.video_filepath, {
+        let events = std::sync::Mutex::new(events.clone());
+        move || events.lock().unwrap().send(AppEvent::DecoderOutput)
+    })?;
+    let record_keys = args.record_keys.clone();
+    let replay_keys = args.replay_keys.clone();
+    let ipc_path = args.ipc_path.clone();
+    let mut model = new_model(args, keymap, Box::new(source), (cols, rows));
+    model.events = Some(events.clone());
+
+    if let Some(path) = record_keys {
+        let mut file = std::fs::File::create(&path).map_err(|e| {
+            VicError::BadCliArg(format!(
+                "failed to create --record-keys {}. {}",
+                path.display(),
+                e
+            ))
+        })?;
+        file.write_all(recording::HEADER.as_bytes())?;
+        model.key_recording = Some(file);
+    }
+    // requests are handled in update(), in between frames and keypresses
+    if let Some(path) = ipc_path {
+        let events = std::sync::Mutex::new(events.clone());
+        let server = ipc::IpcServer::start(&path, move |request| {
+            events.lock().unwrap().send(AppEvent::Ipc(request))
+        })?;
+        model.ipc = Some(server);
+    }
+    // replayed keys go first in line, before the keyboard is even read
+    if let Some(path) = replay_keys {
+        let text = std::fs::read_to_string(&path).map_err(|e| {
+            VicError::BadCliArg(format!(
+                "failed to read --replay-keys {}. {}",
+                path.display(),
+                e
+            ))
+        })?;
+        let replayed_events = recording::parse(&text).map_err(|e| {
+            VicError::BadCliArg(format!(
+                "failed to parse --replay-keys {}\n{}",
+                path.display(),
+                e
+            ))
+        })?;
+        for (at, event) in replayed_events {
+            events.send(AppEvent::Replayed(at, event));
+        }
+        events.send(AppEvent::ReplayFinished);
+    }
+
+    // enum TimerEvent {}
+
+    // terminal_event_broadcaster = new_thread.spawn(forever { await_next_terminal_event() })
+    // timer_broadcaster = timer_thread.spawn(forever { every(100ms).ping() })
+    //
+    // here.watch(broadcaster).on_broadcast(|| {
+    //     update(model, AppEvent::ShowNextFrame);
+    // })
+    // here.watch(terminal_event_broadcaster).on_broadcast(|event| {
+    //     update(model, event);
+    // })
+    //
+    // maybe tokio::join!{task1, task2}
+    // or
+    // tokio::select!{task1, task2}
+    // https://tokio.rs/tokio/tutorial/select
+
+    // let forever = tokio::spawn(async {
+    //     let mut interval = tokio::time::interval(std::time::Duration::from_millis(50));
+    //     loop {
+    //         interval.tick().await;
+    //         model.frame = model.frame_iterator.take_frame();
+    //     }
+    // });
+
+    // also a way to sleep forever maybe: std::future::pending().await;
+    return Ok(model);
+}
+
+// #[tokio::main]
+pub fn main() {
+    let program_result = Program {
+        init,
+        view,
+        update,
+        tick_interval,
+    }
+    .run();
+    match program_result {
+        Ok(mut m) => {
+            m.frame_iterator.stop_decoder();
+            // tell ipc clients the session is over, and stop listening, before exporting
+            if let Some(mut ipc) = m.ipc.take() {
+                ipc.push("finish", Value::Null);
+            }
+            // before exporting, which could take a while
+            if let Some(format) = m.print_format {
+                match report::format_report(
+                    format,
+                    &m.video_path,
+                    m.VIDEO_METADATA.duration_secs,
+                    &m.markers,
+                    &m.segments,
+                    &m.ranges,
+                    &m.export_options,
+                ) {
+                    Ok(report) => print!("{}", report),
+                    Err(msg) => {
+                        // only when the outputs couldn't be planned, like after ctrl+c
+                        eprintln!("Error: failed to --print the session. {}\n", msg);
+                        std::process::exit(1);
+                    }
+                }
+            }
+            if m.markers.len() == 0 && m.ranges.len() == 0 && m.still_frames.len() == 0 {
+                return;
+            }
+
+            if let Some(msg) = &m.export_error {
+                log!("Error: {}", msg);
+                eprintln!("Error: {}\n", msg);
+                std::process::exit(1);
+            }
+
+            // already planned and checked by finish(), or ctrl+c
+            let all_jobs = std::mem::take(&mut m.jobs);
+            for cmd in all_jobs.iter().flat_map(|job| &job.cmds) {
+                log!("a recipe: {}", recipe::cmd_to_sh(cmd));
+            }
+
+            match m.export_options.dry_run {
+                true => {
+                    let cmds = all_jobs
+                        .iter()
+                        .flat_map(|job| &job.cmds)
+                        .collect::<Vec<&std::process::Command>>();
+                    let recipe = recipe::format_recipe(&cmds, m.export_options.dry_run_format);
+                    eprintln!("Here is the recipe from your dry run:\n");
+                    println!("{}", recipe);
+                    eprintln!("");
+                }
+                false => {
+                    let results = jobs::run_jobs(all_jobs, m.export_options.num_jobs);
+                    jobs::print_summary(&results);
+                    if results.iter().any(|(_, result)| result.is_err()) {
+                        std::process::exit(1);
+                    }
+                }
+            }
+        }
+        Err(e) => {
+            // app failed; explain why
+            log!("Error: {:?}", e);
+            eprintln!("Error: {}\n", e);
+            std::process::exit(e.exit_code());
+        }
+    };
+}
+
+// --- TESTS --- //
+
+#[cfg(test)]
+fn synthetic_model() -> Model {
+    // a minute of 10fps test pattern, no ffmpeg needed.
+    // paused, so playback doesn't depend on the clock
+    let args = parse_cli_args(
+        &mut pico_args::Arguments::from_vec(vec!["synthetic.mp4".into()]),
+        ConfigDefaults::default(),
+        true,
+    )
+    .unwrap();
+    let source = crate::frames::SyntheticSource::new(32, 18, 10.0, 60.0).unwrap();
+    let keymap = Keymap::preset("default").unwrap();
+    let mut m = new_model(args, keymap, Box::new(source), (80, 24));
+    m.paused = true;
+    return m;
+}
+
+#[cfg(test)]
+fn press(code: KeyCode) -> Msg<AppEvent> {
+    return Msg::Terminal(Event::Key(KeyEvent::new(code, KeyModifiers::NONE)));
+}
+
+#[cfg(test)]
+fn run_headless(msgs: Vec<Msg<AppEvent>>) -> (Model, VirtualTerminal) {
+    // the synthetic video is drawn 11 rows tall, and the player bar starts below it
+    let mut screen = VirtualTerminal::new(80, 24);
+    let m = Program {
+        init: |_| Ok(synthetic_model()),
+        view,
+        update,
+        tick_interval,
+    }
+    .run_headless(&mut screen, msgs)
+    .unwrap();
+    return (m, screen);
+}
+
+#[cfg(test)]
+fn below_video(m: &Model, screen: &VirtualTerminal) -> String {
+    // the video itself depends on chafa's version and symbols,
+    // so snapshots only cover the player bar and everything under it
+    return screen.lines()[m.frame_iterator.output_rows as usize..]
+        .join("\n")
+        .trim_end()
+        .to_string();
+}
+
+#[cfg(test)]
+#[test]
+fn test_seeking_and_marking_without_ffmpeg() {
+    let mut m = synthetic_model();
+    for msg in [
+        press(KeyCode::Right), // 5s
+        press(KeyCode::Char('m')),
+        press(KeyCode::Char('l')), // 20s
+        press(KeyCode::Char('m')),
+        press(KeyCode::Char('9')), // 54s
+        press(KeyCode::Char('l')), // past the end
+        press(KeyCode::Char('J')), // back to the 20s marker
+    ] {
+        update(&mut m, msg);
+    }
+    assert_eq!(m.markers, vec![5.0, 20.0]);
+    assert_eq!(m.segments.len(), 3);
+    assert_eq!(m.frame_number, 200);
+    assert!(!m.frame.is_empty());
+}
+
+#[cfg(test)]
+#[test]
+fn test_reaching_the_end_pauses_or_loops() {
+    let play_ticks = |m: &mut Model, num_ticks: u32| {
+        // 100ms per tick at 10fps, so one frame each
+        let mut now = std::time::Instant::now();
+        m.replay_time = Some(now);
+        update(m, press(KeyCode::Char(' ')));
+        for _ in 0..num_ticks {
+            now += std::time::Duration::from_millis(100);
+            m.replay_time = Some(now);
+            update(m, Msg::Tick);
+        }
+    };
+
+    // stops on the last of 600 frames, even with ticks to spare
+    let mut m = synthetic_model();
+    update(&mut m, press(KeyCode::Char('9'))); // 54s
+    play_ticks(&mut m, 70);
+    assert_eq!(m.frame_number, 599);
+    assert!(m.paused);
+    assert_eq!(m.status_msg.as_ref().unwrap().0, "end of video");
+
+    let mut m = synthetic_model();
+    m.looping = true;
+    update(&mut m, press(KeyCode::Char('9')));
+    play_ticks(&mut m, 60);
+    assert_eq!(m.frame_number, 0);
+    assert!(!m.paused);
+
+    // ffprobe's duration can be a little longer than the decoded frames,
+    // so seeking past the end finds the actual final frame
+    let mut m = synthetic_model();
+    m.VIDEO_METADATA.duration_secs = 60.5;
+    update(&mut m, press(KeyCode::Char('9')));
+    update(&mut m, press(KeyCode::Char('l'))); // 69s
+    assert_eq!(m.frame_number, 599);
+    assert!(!m.frame.is_empty());
+    assert!(m.paused);
+}
+
+#[cfg(test)]
+#[test]
+fn test_undoing_and_redoing_edits() {
+    let mut m = synthetic_model();
+    for msg in [
+        press(KeyCode::Char('3')), // 18s
+        press(KeyCode::Char('m')),
+        press(KeyCode::Char('6')), // 36s
+        press(KeyCode::Char('m')),
+        press(KeyCode::Char('s')), // discard 18s - 36s
+        press(KeyCode::Char('i')),
+        press(KeyCode::Char('9')), // 54s
+        press(KeyCode::Char('o')),
+        press(KeyCode::Char('J')), // back to the 36s marker
+        press(KeyCode::Char('M')),
+    ] {
+        update(&mut m, msg);
+    }
+    assert_eq!(m.markers, vec![18.0]);
+    assert_eq!(m.ranges.len(), 1);
+    assert!(matches!(m.hovered_item.mode, HoverMode::Segments));
+
+    // the deleted marker comes back hovered, with the playhead on it
+    update(&mut m, press(KeyCode::Char('u')));
+    assert_eq!(m.markers, vec![18.0, 36.0]);
+    assert!(matches!(
+        m.hovered_item,
+        Hovering {
+            mode: HoverMode::Markers,
+            position: 1
+        }
+    ));
+    assert_eq!(m.frame_number, 360);
+    assert_eq!(
+        m.status_msg.as_ref().unwrap().0,
+        "undid delete marker at 0:36"
+    );
+
+    update(&mut m, press(KeyCode::Char('u')));
+    assert_eq!(m.ranges.len(), 0);
+    update(&mut m, press(KeyCode::Char('u')));
+    assert!(m.segments.iter().all(|segment| segment.kept));
+    assert!(matches!(m.hovered_item.mode, HoverMode::Segments));
+
+    let redo = || {
+        Msg::Terminal(Event::Key(KeyEvent::new(
+            KeyCode::Char('r'),
+            KeyModifiers::CONTROL,
+        )))
+    };
+    update(&mut m, redo());
+    assert!(!m.segments[1].kept);
+    update(&mut m, redo());
+    assert_eq!(m.ranges.len(), 1);
+    update(&mut m, redo());
+    assert_eq!(m.markers, vec![18.0]);
+    assert!(matches!(m.hovered_item.mode, HoverMode::Segments));
+    update(&mut m, redo());
+    assert_eq!(m.status_msg.as_ref().unwrap().0, "nothing to redo");
+}
+
+#[cfg(test)]
+#[test]
+fn test_quitting_with_a_bad_output_filename_keeps_the_session() {
+    let mut m = synthetic_model();
+    m.export_options.output_template = Some("{label}".to_string());
+    update(&mut m, press(KeyCode::Right)); // 5s
+    update(&mut m, press(KeyCode::Char('m')));
+    assert!(matches!(
+        update(&mut m, press(KeyCode::Char('q'))),
+        UpdateResult::Continue
+    ));
+    assert!(m
+        .status_msg
+        .as_ref()
+        .unwrap()
+        .0
+        .starts_with("can't export: "));
+
+    // labeling both segments fixes their filenames
+    for (percent, label) in [('0', 'a'), ('9', 'b')] {
+        update(&mut m, press(KeyCode::Char(percent)));
+        update(&mut m, press(KeyCode::Char('r')));
+        update(&mut m, press(KeyCode::Char(label)));
+        update(&mut m, press(KeyCode::Enter));
+    }
+    assert!(matches!(
+        update(&mut m, press(KeyCode::Char('q'))),
+        UpdateResult::Finish
+    ));
+    let outpaths = m
+        .jobs
+        .iter()
+        .map(|job| job.outpath.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        outpaths,
+        vec![std::path::PathBuf::from("a"), std::path::PathBuf::from("b")]
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_ctrl_c_quits_even_when_nothing_can_be_exported() {
+    let ctrl_c = || {
+        Msg::Terminal(Event::Key(KeyEvent::new(
+            KeyCode::Char('c'),
+            KeyModifiers::CONTROL,
+        )))
+    };
+    let mut m = synthetic_model();
+    m.export_options.output_template = Some("{label}".to_string());
+    update(&mut m, press(KeyCode::Right)); // 5s
+    update(&mut m, press(KeyCode::Char('m')));
+    assert!(matches!(update(&mut m, ctrl_c()), UpdateResult::Finish));
+    assert!(m.jobs.is_empty());
+    assert!(m.export_error.is_some());
+
+    // even while typing a label
+    let mut m = synthetic_model();
+    update(&mut m, press(KeyCode::Char('r')));
+    assert!(m.label_prompt.is_some());
+    assert!(matches!(update(&mut m, ctrl_c()), UpdateResult::Finish));
+    assert!(m.export_error.is_none());
+}
+
+#[cfg(test)]
+#[test]
+fn test_screen_after_seeking_and_marking() {
+    let (m, screen) = run_headless(vec![
+        press(KeyCode::Right), // 5s
+        press(KeyCode::Char('m')),
+        press(KeyCode::Char('l')), // 20s
+        press(KeyCode::Char('m')),
+    ]);
+    let time_row = &screen.lines()[m.frame_iterator.output_rows as usize - 1];
+    assert!(time_row.starts_with(" 0:20 / 1:00 "));
+    assert!(time_row.ends_with(" ||"));
+    assert_eq!(
+        below_video(&m, &screen),
+        "\
+┌─┬────────v───────────────────────────┐
+└─┴────────┴───────────────────────────┘
+ segment 2 of 3                   help?"
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_screen_after_discarding_and_deleting() {
+    let discard_middle_segment = vec![
+        press(KeyCode::Char('3')), // 18s
+        press(KeyCode::Char('m')),
+        press(KeyCode::Char('6')), // 36s
+        press(KeyCode::Char('m')),
+        press(KeyCode::Char('s')),
+    ];
+    let (m, screen) = run_headless(discard_middle_segment);
+    assert_eq!(
+        below_video(&m, &screen),
+        "\
+┌─────────┬╌╌╌╌╌╌╌╌╌╌v─────────────────┐
+└─────────┴╌╌╌╌╌╌╌╌╌╌┴─────────────────┘
+ segment 2 of 3 (discarded)       help?"
+    );
+
+    // deleting the 18s marker merges the discarded segment into the kept one before it.
+    // the shorter segment text shouldn't leave " (discarded)" behind
+    let (m, screen) = run_headless(vec![
+        press(KeyCode::Char('3')),
+        press(KeyCode::Char('m')),
+        press(KeyCode::Char('6')),
+        press(KeyCode::Char('m')),
+        press(KeyCode::Char('s')),
+        press(KeyCode::Char('J')), // to the 18s marker
+        press(KeyCode::Char('M')),
+    ]);
+    assert_eq!(
+        below_video(&m, &screen),
+        "\
+┌─────────v──────────┬─────────────────┐
+└────────────────────┴─────────────────┘
+ segment 1 of 2                   help?"
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_screen_after_showing_controls_and_resizing() {
+    // 24 rows is too short for the controls, so the screen scrolled,
+    // but the resize clears it and redraws everything from the top
+    let (m, screen) = run_headless(vec![
+        press(KeyCode::Char('?')),
+        press(KeyCode::Char('5')), // 30s
+        Msg::Terminal(Event::Resize(80, 30)),
+    ]);
+    assert_eq!(screen.lines().len(), 30);
+    assert_eq!(
+        below_video(&m, &screen),
+        "\
+┌─────────────────v────────────────────┐
+└──────────────────────────────────────┘
+ segment 1 of 1                   help?
+
+     m = make marker
+     s = discard segment
+     r = label segment
+   i/o = set in/out point
+ space = unpause
+   j/l = back/forwards 15 secs
+   ←/→ = back/forwards 5 secs
+   0-9 = skip to 0%, 10%, etc
+     . = advance one frame
+     p = save frame as png
+  u/^r = undo/redo
+     I = show video info
+     ? = hide controls
+     q = quit"
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_screen_at_a_tiny_width() {
+    // narrower than the "help?" text, which shouldn't crash the view
+    let args = parse_cli_args(
+        &mut pico_args::Arguments::from_vec(vec!["synthetic.mp4".into(), "-w".into(), "5".into()]),
+        ConfigDefaults::default(),
+        true,
+    )
+    .unwrap();
+    let source = crate::frames::SyntheticSource::new(32, 18, 10.0, 60.0).unwrap();
+    let keymap = Keymap::preset("default").unwrap();
+    let mut m = new_model(args, keymap, Box::new(source), (80, 24));
+    m.paused = true;
+    assert!(m.frame_iterator.output_cols < 4);
+
+    let mut screen = VirtualTerminal::new(80, 24);
+    Program {
+        init: move |_| Ok(m),
+        view,
+        update,
+        tick_interval,
+    }
+    .run_headless(&mut screen, vec![press(KeyCode::Right)])
+    .unwrap();
+}
+
+#[cfg(test)]
+#[test]
+fn test_screen_with_info_panel() {
+    // already probed, since the synthetic video has no file for ffprobe
+    let mut m = synthetic_model();
+    let streams_json = r#"{
+        "streams": [{ "codec_type": "video", "codec_name": "vp9", "pix_fmt": "yuv420p",
+            "width": 32, "height": 18, "r_frame_rate": "10/1", "avg_frame_rate": "10/1" }],
+        "format": { "format_long_name": "Matroska / WebM", "duration": "60.000000" }
+    }"#;
+    m.info = Some(probe::parse_ffprobe_json(streams_json, "{}"));
+
+    let mut screen = VirtualTerminal::new(80, 40);
+    let m = Program {
+        init: move |_| Ok(m),
+        view,
+        update,
+        tick_interval,
+    }
+    .run_headless(&mut screen, vec![press(KeyCode::Char('I'))])
+    .unwrap();
+    assert_eq!(
+        below_video(&m, &screen),
+        "\
+v──────────────────────────────────────┐
+└──────────────────────────────────────┘
+ segment 1 of 1                   help?
+
+ container ..... Matroska / WebM
+ size .......... 32x18
+ duration ...... 60.000 secs
+ codec ......... vp9
+ pixel format .. yuv420p
+ framerate ..... 10 fps, constant
+ frames ........ about 600
+ keyframes ..... fewer than 2 in the first 30 secs
+ bitrate ....... unknown
+ rotation ...... 0°
+ audio ......... none
+ subtitles ..... none"
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_info_panel_probes_in_the_background() {
+    // the probe's own event isn't delivered before the script ends,
+    // so the panel can be checked while it waits, then given a result
+    let mut m = synthetic_model();
+    let mut screen = VirtualTerminal::new(80, 40);
+    let mut m = Program {
+        init: move |events| {
+            m.events = Some(events);
+            Ok(m)
+        },
+        view,
+        update,
+        tick_interval,
+    }
+    .run_headless(&mut screen, vec![press(KeyCode::Char('I'))])
+    .unwrap();
+    assert!(m.is_probing);
+    assert!(below_video(&m, &screen).ends_with("\n\n probing..."));
+
+    update(
+        &mut m,
+        Msg::App(AppEvent::Probed(Err("ffprobe not found".to_string()))),
+    );
+    view(&m, &mut screen);
+    assert!(!m.is_probing);
+    assert!(below_video(&m, &screen).ends_with("\n\n failed to probe: ffprobe not found"));
+}
+
+#[cfg(test)]
+#[test]
+fn test_replayed_keys_follow_their_recorded_times() {
+    // playback between replayed keys goes by their times, not the clock,
+    // and recording them gives back the same times
+    let path = std::env::temp_dir().join(format!("vic_test_keys_{}", std::process::id()));
+    let mut m = synthetic_model();
+    m.key_recording = Some(std::fs::File::create(&path).unwrap());
+    for (at, code) in [
+        (1.0, KeyCode::Char(' ')), // unpause
+        (3.5, KeyCode::Char('m')),
+        (4.0, KeyCode::Char(' ')), // pause
+    ] {
+        let event = Event::Key(KeyEvent::new(code, KeyModifiers::NONE));
+        update(&mut m, Msg::App(AppEvent::Replayed(at, event)));
+    }
+    update(&mut m, Msg::App(AppEvent::ReplayFinished));
+
+    assert_eq!(m.markers, vec![2.5]);
+    assert_eq!(m.frame_number, 30);
+    assert!(m.paused);
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "1.000 key space\n3.500 key m\n4.000 key space\n"
+    );
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[cfg(test)]
+#[test]
+fn test_leftover_cli_args_are_rejected() {
+    let parse = |args: &[&str]| -> Result<(), VicError> {
+        let mut pargs =
+            pico_args::Arguments::from_vec(args.iter().map(std::ffi::OsString::from).collect());
+        parse_cli_args(&mut pargs, ConfigDefaults::default(), true).map_err(VicError::BadCliArg)?;
+        return reject_leftover_args(pargs);
+    };
+    assert!(parse(&["video.mp4", "-w", "20", "--dry-run=sh"]).is_ok());
+    assert!(parse(&["video.mp4", "other.mp4"]).is_err());
+    assert!(parse(&["video.mp4", "-W", "20"]).is_err());
+}
+
+#[cfg(all(test, unix))]
+#[test]
+fn test_ipc_client_edits_and_hears_about_it() {
+    // a local client on a real socket. its requests are handled by update(),
+    // like during a session, so the replies and events come back in a known order
+    let path = std::env::temp_dir().join(format!("vic_test_ipc_{}.sock", std::process::id()));
+    let (sender, requests) = std::sync::mpsc::channel();
+    let sender = std::sync::Mutex::new(sender);
+    let mut m = synthetic_model();
+    m.ipc = Some(
+        ipc::IpcServer::start(&path, move |request| {
+            sender.lock().unwrap().send(request).unwrap()
+        })
+        .unwrap(),
+    );
+
+    let mut client = std::os::unix::net::UnixStream::connect(&path).unwrap();
+    let mut lines = std::io::BufReader::new(client.try_clone().unwrap()).lines();
+    let mut ask = |m: &mut Model, request: &str| {
+        writeln!(client, "{}", request).unwrap();
+        let request = requests
+            .recv_timeout(std::time::Duration::from_secs(5))
+            .unwrap();
+        return update(m, Msg::App(AppEvent::Ipc(request)));
+    };
+    ask(&mut m, r#"{"command": ["subscribe"], "request_id": 1}"#);
+    ask(&mut m, r#"{"command": ["seek", 12.5]}"#);
+    ask(&mut m, r#"{"command": ["add-marker"]}"#);
+    ask(&mut m, r#"{"command": ["add-marker", 3]}"#);
+    ask(&mut m, r#"{"command": ["remove-marker", 7]}"#);
+    update(&mut m, press(KeyCode::Char('u'))); // ipc edits can be undone too
+    ask(
+        &mut m,
+        r#"{"command": ["get-position"], "request_id": "p"}"#,
+    );
+    // a failed export is reported, and the session goes on
+    m.export_options.output_template = Some("{label}".to_string());
+    assert!(matches!(
+        ask(&mut m, r#"{"command": ["export"]}"#),
+        UpdateResult::Continue
+    ));
+    m.export_options.output_template = None;
+    assert!(matches!(
+        ask(&mut m, r#"{"command": ["export"]}"#),
+        UpdateResult::Finish
+    ));
+    assert_eq!(m.jobs.len(), 2);
+
+    let expected = vec![
+        r#"{"request_id":1,"error":"success","data":null}"#,
+        r#"{"event":"pause","data":true}"#,
+        r#"{"event":"markers","data":[]}"#,
+        r#"{"event":"position","data":0}"#,
+        r#"{"error":"success","data":null}"#,
+        r#"{"event":"position","data":12.5}"#,
+        r#"{"error":"success","data":null}"#,
+        r#"{"event":"markers","data":[12.5]}"#,
+        r#"{"error":"success","data":null}"#,
+        r#"{"event":"markers","data":[3,12.5]}"#,
+        r#"{"error":"no marker at 0:07","data":null}"#,
+        r#"{"event":"markers","data":[12.5]}"#,
+        r#"{"request_id":"p","error":"success","data":{"position":12.5,"frame":125,"paused":true}}"#,
+        r#"{"error":"output template {label} makes an invalid filename \"\"","data":null}"#,
+        r#"{"error":"success","data":null}"#,
+    ];
+    for line in expected {
+        assert_eq!(lines.next().unwrap().unwrap(), line);
+    }
+    assert_eq!(m.markers, vec![12.5]);
+
+    // malformed requests are answered without reaching the app
+    writeln!(client, "nope").unwrap();
+    assert_eq!(
+        lines.next().unwrap().unwrap(),
+        r#"{"error":"bad json: expected null at character 2","data":null}"#
+    );
+    drop(m);
+    assert!(!path.exists());
+}
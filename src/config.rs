@@ -0,0 +1,301 @@
+// the config file, at $XDG_CONFIG_HOME/vic/config.toml, or wherever --config says
+//
+// only a small subset of toml, which is all vic needs:
+//
+//   # comments
+//   [section]
+//   key = "string"
+//   key = 'literal string'
+//   key = 123
+//   key = true
+//   key = ["list", "of", "strings"]
+//
+// each value is stored with its section, like keys.make-marker
+
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Str(String),
+    Int(i64),
+    Bool(bool),
+    List(Vec<String>),
+}
+
+impl Value {
+    pub fn as_strings(&self) -> Option<Vec<String>> {
+        // a single string is a list of one
+        return match self {
+            Value::Str(s) => Some(vec![s.clone()]),
+            Value::List(items) => Some(items.clone()),
+            _ => None,
+        };
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ConfigFile {
+    pub path: Option<PathBuf>,         // None if there was no file
+    pub entries: Vec<(String, Value)>, // like ("keys.preset", Str("vim")), in file order
+}
+
+impl ConfigFile {
+    pub fn section(&self, name: &str) -> Vec<(&str, &Value)> {
+        let prefix = format!("{}.", name);
+        return self
+            .entries
+            .iter()
+            .filter_map(|(key, value)| key.strip_prefix(&prefix).map(|key| (key, value)))
+            .collect();
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        return self
+            .entries
+            .iter()
+            .rev() // later entries win
+            .find(|(other_key, _)| other_key == key)
+            .map(|(_, value)| value);
+    }
+
+    pub fn get_parsed<T>(&self, key: &str) -> Result<Option<T>, String>
+    where
+        T: std::str::FromStr,
+        T::Err: std::fmt::Display,
+    {
+        // reuses the same parsing as cli args, so `width = 80` works like `-w 80`
+        let text = match self.get(key) {
+            None => return Ok(None),
+            Some(Value::Str(s)) => s.clone(),
+            Some(Value::Int(n)) => n.to_string(),
+            Some(Value::Bool(b)) => b.to_string(),
+            Some(Value::List(_)) => {
+                return Err(format!("{} should not be a list{}", key, self.in_file()))
+            }
+        };
+        return text
+            .parse::<T>()
+            .map(Some)
+            .map_err(|e| format!("failed to parse {}{}. {}", key, self.in_file(), e));
+    }
+
+    pub fn check_known(&self, known_keys: &[&str], known_sections: &[&str]) -> Result<(), String> {
+        // catches typos, which would otherwise be silently ignored
+        for (key, _) in &self.entries {
+            let is_known = known_keys.contains(&key.as_str())
+                || known_sections
+                    .iter()
+                    .any(|section| key.starts_with(&format!("{}.", section)));
+            if !is_known {
+                return Err(format!("unknown setting {}{}", key, self.in_file()));
+            }
+        }
+        return Ok(());
+    }
+
+    pub fn in_file(&self) -> String {
+        // for error messages, like "unknown setting widht in /home/me/.config/vic/config.toml"
+        return match &self.path {
+            Some(path) => format!(" in {}", path.display()),
+            None => "".to_string(),
+        };
+    }
+}
+
+pub fn default_path() -> Option<PathBuf> {
+    // $XDG_CONFIG_HOME/vic/config.toml, else ~/.config/vic/config.toml
+    let config_home = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    return Some(config_home.join("vic").join("config.toml"));
+}
+
+pub fn load_default() -> Result<ConfigFile, String> {
+    // a missing config file is fine; it's optional
+    return match default_path() {
+        Some(path) if path.is_file() => load(path),
+        _ => Ok(ConfigFile::default()),
+    };
+}
+
+pub fn load(path: PathBuf) -> Result<ConfigFile, String> {
+    let text = std::fs::read_to_string(&path)
+        .map_err(|e| format!("failed to read config file {}: {}", path.display(), e))?;
+    let entries = parse(&text).map_err(|e| format!("{} in {}", e, path.display()))?;
+    return Ok(ConfigFile {
+        path: Some(path),
+        entries: entries,
+    });
+}
+
+pub fn parse(text: &str) -> Result<Vec<(String, Value)>, String> {
+    let mut entries = vec![];
+    let mut section = "".to_string();
+    for (i, line) in text.lines().enumerate() {
+        let line_number = i + 1;
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[') {
+            section = name
+                .strip_suffix(']')
+                .ok_or(format!("missing ] on line {}", line_number))?
+                .trim()
+                .to_string();
+            continue;
+        }
+        let (key, value) = line
+            .split_once('=')
+            .ok_or(format!("expected key = value on line {}", line_number))?;
+        let key = unquote(key.trim()).unwrap_or(key.trim().to_string());
+        let value = parse_value(value.trim())
+            .map_err(|e| format!("{} for {} on line {}", e, key, line_number))?;
+        let full_key = match section.is_empty() {
+            true => key,
+            false => format!("{}.{}", section, key),
+        };
+        entries.push((full_key, value));
+    }
+    return Ok(entries);
+}
+
+fn strip_comment(line: &str) -> &str {
+    // a # starts a comment, unless it's inside a string, like "#"
+    let mut quote: Option<char> = None;
+    let mut is_escaped = false;
+    for (i, c) in line.char_indices() {
+        match (quote, c) {
+            (Some('"'), '\\') if !is_escaped => {
+                is_escaped = true;
+                continue;
+            }
+            (Some(q), c) if c == q && !is_escaped => quote = None,
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (None, '#') => return &line[..i],
+            _ => (),
+        }
+        is_escaped = false;
+    }
+    return line;
+}
+
+fn parse_value(text: &str) -> Result<Value, String> {
+    if let Some(s) = unquote(text) {
+        return Ok(Value::Str(s));
+    }
+    if let Some(inner) = text.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+        let items = split_list(inner)?
+            .iter()
+            .map(|item| unquote(item).ok_or(format!("expected a quoted string, found {}", item)))
+            .collect::<Result<Vec<String>, String>>()?;
+        return Ok(Value::List(items));
+    }
+    return match text {
+        "true" => Ok(Value::Bool(true)),
+        "false" => Ok(Value::Bool(false)),
+        _ => text
+            .replace('_', "")
+            .parse::<i64>()
+            .map(Value::Int)
+            .map_err(|_| format!("unknown value {}", text)),
+    };
+}
+
+fn split_list(inner: &str) -> Result<Vec<String>, String> {
+    // split on commas outside of quotes, allowing a trailing comma
+    let mut items = vec![];
+    let mut item = String::new();
+    let mut quote: Option<char> = None;
+    let mut is_escaped = false;
+    for c in inner.chars() {
+        match (quote, c) {
+            (None, ',') => {
+                items.push(item.trim().to_string());
+                item.clear();
+                continue;
+            }
+            (Some('"'), '\\') if !is_escaped => is_escaped = true,
+            (Some(q), c) if c == q && !is_escaped => quote = None,
+            (None, '"') | (None, '\'') => quote = Some(c),
+            _ => is_escaped = false,
+        }
+        item.push(c);
+    }
+    if quote.is_some() {
+        return Err("unclosed quote".to_string());
+    }
+    items.push(item.trim().to_string());
+    return Ok(items.into_iter().filter(|item| !item.is_empty()).collect());
+}
+
+fn unquote(text: &str) -> Option<String> {
+    // "basic strings" have escapes, 'literal strings' don't.
+    // settings get printed back out with json::quote(), whose escapes are also toml's
+    if text.len() >= 2 && text.starts_with('\'') && text.ends_with('\'') {
+        return Some(text[1..text.len() - 1].to_string());
+    }
+    if !(text.len() >= 2 && text.starts_with('"') && text.ends_with('"')) {
+        return None;
+    }
+    let mut unquoted = String::new();
+    let mut chars = text[1..text.len() - 1].chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n') => unquoted.push('\n'),
+                Some('r') => unquoted.push('\r'),
+                Some('t') => unquoted.push('\t'),
+                Some('u') => {
+                    // like \u001b, which json::quote() writes for control characters
+                    let hex = chars.by_ref().take(4).collect::<String>();
+                    match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                        Some(c) => unquoted.push(c),
+                        None => unquoted.push_str(&format!("u{}", hex)),
+                    }
+                }
+                Some(other) => unquoted.push(other), // like \" and \\
+                None => unquoted.push('\\'),
+            },
+            c => unquoted.push(c),
+        }
+    }
+    return Some(unquoted);
+}
+
+#[cfg(test)]
+#[test]
+fn test_parsing_config_subset() {
+    let text = r##"
+        # top-level settings
+        width = 80
+
+        [keys]
+        preset = "vim"   # a comment
+        make-marker = ["m", "#", 'enter',]
+        "quit" = "ctrl+q"
+        help = []
+        blocky = false
+    "##;
+    assert_eq!(
+        parse(text),
+        Ok(vec![
+            ("width".to_string(), Value::Int(80)),
+            ("keys.preset".to_string(), Value::Str("vim".to_string())),
+            (
+                "keys.make-marker".to_string(),
+                Value::List(vec!["m".to_string(), "#".to_string(), "enter".to_string()])
+            ),
+            ("keys.quit".to_string(), Value::Str("ctrl+q".to_string())),
+            ("keys.help".to_string(), Value::List(vec![])),
+            ("keys.blocky".to_string(), Value::Bool(false)),
+        ])
+    );
+    assert!(parse("[keys]\nquit = q").is_err());
+    assert!(parse("[keys\nquit = 'q'").is_err());
+
+    // settings are printed with json's quoting, which toml reads the same way
+    let text = "say \"hi\"\\\r\n\t\u{1b}";
+    assert_eq!(unquote(&crate::json::quote(text)), Some(text.to_string()));
+}
@@ -0,0 +1,101 @@
+// everything that can stop vic from starting, or interrupt a session
+//
+// each kind of error has its own exit code, so scripts can tell them apart:
+//
+//   1 .. an export failed, like an ffmpeg error or an output that already exists
+//   2 .. bad command-line argument or config file
+//   3 .. ffprobe not found
+//   4 .. ffmpeg not found
+//   5 .. no video stream that vic understands
+//   6 .. ffmpeg or ffprobe failed to read the video
+//   7 .. other I/O error, like with the terminal
+
+#[derive(Debug)]
+pub enum VicError {
+    FfprobeMissing,
+    FfmpegMissing,
+    UnsupportedStream(String),
+    DecodeFailed(String),
+    BadCliArg(String),
+    BadConfig(String),
+    Io(std::io::Error),
+}
+
+impl VicError {
+    pub fn exit_code(&self) -> i32 {
+        return match self {
+            VicError::BadCliArg(_) => 2,
+            VicError::BadConfig(_) => 2,
+            VicError::FfprobeMissing => 3,
+            VicError::FfmpegMissing => 4,
+            VicError::UnsupportedStream(_) => 5,
+            VicError::DecodeFailed(_) => 6,
+            VicError::Io(_) => 7,
+        };
+    }
+
+    pub fn from_spawn_error(program: &str, e: std::io::Error) -> Self {
+        // a missing program is the most common reason a subprocess doesn't start
+        return match (e.kind(), program) {
+            (std::io::ErrorKind::NotFound, "ffprobe") => VicError::FfprobeMissing,
+            (std::io::ErrorKind::NotFound, "ffmpeg") => VicError::FfmpegMissing,
+            _ => VicError::Io(e),
+        };
+    }
+}
+
+impl std::fmt::Display for VicError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        return match self {
+            VicError::FfprobeMissing => write!(
+                f,
+                "ffprobe was not found.\nit comes with ffmpeg; install ffmpeg and make sure ffprobe is on your $PATH"
+            ),
+            VicError::FfmpegMissing => write!(
+                f,
+                "ffmpeg was not found.\ninstall ffmpeg and make sure it's on your $PATH"
+            ),
+            VicError::UnsupportedStream(msg) => write!(
+                f,
+                "{}\nvic needs a video stream with a known size, framerate, and duration",
+                msg
+            ),
+            VicError::DecodeFailed(msg) => write!(
+                f,
+                "failed to read the video. {}\ncheck that the file exists and plays with ffplay",
+                msg
+            ),
+            VicError::BadCliArg(msg) => write!(f, "{}\nsee vic --help", msg),
+            VicError::BadConfig(msg) => write!(
+                f,
+                "{}\nsee CONFIG in vic --help, or move the config file aside to use the defaults",
+                msg
+            ),
+            VicError::Io(e) => write!(f, "{}", e),
+        };
+    }
+}
+
+impl std::error::Error for VicError {}
+
+impl From<std::io::Error> for VicError {
+    fn from(e: std::io::Error) -> Self {
+        return VicError::Io(e);
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_missing_programs_get_their_own_exit_codes() {
+    let not_found = || std::io::Error::from(std::io::ErrorKind::NotFound);
+    let ffprobe_err = VicError::from_spawn_error("ffprobe", not_found());
+    let ffmpeg_err = VicError::from_spawn_error("ffmpeg", not_found());
+    let denied_err = VicError::from_spawn_error(
+        "ffmpeg",
+        std::io::Error::from(std::io::ErrorKind::PermissionDenied),
+    );
+    assert!(matches!(ffprobe_err, VicError::FfprobeMissing));
+    assert!(matches!(ffmpeg_err, VicError::FfmpegMissing));
+    assert!(matches!(denied_err, VicError::Io(_)));
+    assert_ne!(ffprobe_err.exit_code(), ffmpeg_err.exit_code());
+}
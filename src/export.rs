@@ -0,0 +1,979 @@
+// output filenames and ffmpeg commands for cutting the video on finish
+//
+// filenames come from a template like `{stem}_{index:02}_{label}.{ext}`,
+// where each {placeholder} is filled in per output video
+
+use std::path::{Path, PathBuf};
+
+use crate::jobs::Job;
+use crate::recipe::DryRunFormat;
+use crate::timeline::{self, ClipRange, Segment};
+use crate::{log, Logs, Seconds};
+
+pub const DEFAULT_SEGMENT_TEMPLATE: &str = "{stem}_{index}.{ext}";
+pub const DEFAULT_RANGE_TEMPLATE: &str = "{stem}_{label}.{ext}";
+
+#[derive(Debug)]
+pub struct ExportOptions {
+    pub dry_run: bool,
+    pub dry_run_format: DryRunFormat,
+    pub output_template: Option<String>, // else DEFAULT_SEGMENT_TEMPLATE or DEFAULT_RANGE_TEMPLATE
+    pub output_dir: Option<PathBuf>,     // else same dir as source video
+    pub preset: Option<Preset>,          // else let ffmpeg pick codecs for the container
+    pub ffmpeg_args: Vec<String>,        // extra args for each output, after any preset args
+    pub mode: ExportMode,
+    pub width_px: u32, // for animated gif/webp, which are usually smaller than the source
+    pub fps: u32,      // for animated gif/webp
+    pub every_nth_frame: u32, // for image sequences
+    pub audio_format: AudioFormat,
+    pub concat_audio: bool, // join all audio into one file, rather than one file per output
+    pub num_jobs: usize,    // how many ffmpeg commands can run at once
+}
+
+impl Default for ExportOptions {
+    // same as the command-line defaults, for library users
+    fn default() -> Self {
+        return Self {
+            dry_run: false,
+            dry_run_format: DryRunFormat::Plain,
+            output_template: None,
+            output_dir: None,
+            preset: None,
+            ffmpeg_args: vec![],
+            mode: ExportMode::Video,
+            width_px: 480,
+            fps: 15,
+            every_nth_frame: 1,
+            audio_format: AudioFormat::Flac,
+            concat_audio: false,
+            num_jobs: 1,
+        };
+    }
+}
+
+impl ExportOptions {
+    pub fn extension(&self) -> Option<&'static str> {
+        // output extension, if it shouldn't be the same as the source video
+        match self.mode {
+            ExportMode::Video => self.preset.and_then(|preset| preset.extension()),
+            ExportMode::Gif => Some("gif"),
+            ExportMode::Webp => Some("webp"),
+            ExportMode::Frames => Some("png"),
+            ExportMode::Audio => Some(self.audio_format.extension()),
+        }
+    }
+}
+
+// what kind of file to make from each kept segment or range
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportMode {
+    Video,
+    Gif,
+    Webp,   // animated
+    Frames, // image sequence, like video_0_00001.png, video_0_00002.png, ...
+    Audio,  // no video stream
+}
+
+impl std::str::FromStr for ExportMode {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "video" => Ok(ExportMode::Video),
+            "gif" => Ok(ExportMode::Gif),
+            "webp" => Ok(ExportMode::Webp),
+            "frames" => Ok(ExportMode::Frames),
+            "audio" => Ok(ExportMode::Audio),
+            _ => Err(format!(
+                "unknown export mode {}\nexpected one of video, gif, webp, frames, audio",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AudioFormat {
+    Wav,
+    Flac,
+    Mp3,
+    Opus,
+}
+
+impl std::str::FromStr for AudioFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "wav" => Ok(AudioFormat::Wav),
+            "flac" => Ok(AudioFormat::Flac),
+            "mp3" => Ok(AudioFormat::Mp3),
+            "opus" => Ok(AudioFormat::Opus),
+            _ => Err(format!(
+                "unknown audio format {}\nexpected one of wav, flac, mp3, opus",
+                s
+            )),
+        }
+    }
+}
+
+impl AudioFormat {
+    pub fn ffmpeg_args(&self) -> &'static str {
+        match self {
+            AudioFormat::Wav => "-c:a pcm_s16le",
+            AudioFormat::Flac => "-c:a flac",
+            AudioFormat::Mp3 => "-c:a libmp3lame -q:a 2", // ~190 kbps variable bitrate
+            AudioFormat::Opus => "-c:a libopus -b:a 128k",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            AudioFormat::Wav => "wav",
+            AudioFormat::Flac => "flac",
+            AudioFormat::Mp3 => "mp3",
+            AudioFormat::Opus => "opus",
+        }
+    }
+}
+
+// named bundles of ffmpeg encoding args, for common reasons to cut a video
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Preset {
+    WebH264,
+    ArchiveFfv1,
+    SmallHevc,
+    ProresProxy,
+    SameAsSource,
+}
+
+impl std::str::FromStr for Preset {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "web-h264" => Ok(Preset::WebH264),
+            "archive-ffv1" => Ok(Preset::ArchiveFfv1),
+            "small-hevc" => Ok(Preset::SmallHevc),
+            "prores-proxy" => Ok(Preset::ProresProxy),
+            "same-as-source" => Ok(Preset::SameAsSource),
+            _ => Err(format!(
+                "unknown preset {}\nexpected one of web-h264, archive-ffv1, small-hevc, prores-proxy, same-as-source",
+                s
+            )),
+        }
+    }
+}
+
+impl Preset {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Preset::WebH264 => "web-h264",
+            Preset::ArchiveFfv1 => "archive-ffv1",
+            Preset::SmallHevc => "small-hevc",
+            Preset::ProresProxy => "prores-proxy",
+            Preset::SameAsSource => "same-as-source",
+        }
+    }
+
+    pub fn ffmpeg_args(&self) -> &'static str {
+        match self {
+            // plays almost anywhere, including browsers and phones
+            Preset::WebH264 => {
+                "-c:v libx264 -preset medium -crf 23 -pix_fmt yuv420p -c:a aac -b:a 128k -movflags +faststart"
+            }
+            // lossless and large
+            Preset::ArchiveFfv1 => "-c:v ffv1 -level 3 -g 1 -c:a flac",
+            // slow to encode, but about half the size of h264 at similar quality
+            Preset::SmallHevc => "-c:v libx265 -preset slow -crf 28 -tag:v hvc1 -c:a aac -b:a 96k",
+            // lightweight intermediate for other video editors
+            Preset::ProresProxy => "-c:v prores_ks -profile:v 0 -c:a pcm_s16le",
+            // no re-encoding, so it's fast and lossless.
+            // but cuts can only start on keyframes, so they may be off by a few seconds
+            Preset::SameAsSource => "-c copy",
+        }
+    }
+
+    pub fn extension(&self) -> Option<&'static str> {
+        // some codecs aren't allowed in every container, so override the source extension
+        match self {
+            Preset::WebH264 => Some("mp4"),
+            Preset::ArchiveFfv1 => Some("mkv"),
+            Preset::SmallHevc => Some("mp4"),
+            Preset::ProresProxy => Some("mov"),
+            Preset::SameAsSource => None,
+        }
+    }
+}
+
+pub fn split_ffmpeg_args(args: &str) -> Result<Vec<String>, String> {
+    // split a user-given string like `-vf "scale=640:-2" -an` into separate args,
+    // respecting single and double quotes like a shell would (but without escapes)
+    let mut split_args = Vec::<String>::new();
+    let mut current: Option<String> = None;
+    let mut quote: Option<char> = None;
+    for c in args.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => current.get_or_insert_with(String::new).push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                current.get_or_insert_with(String::new);
+            }
+            (None, c) if c.is_whitespace() => split_args.extend(current.take()),
+            (None, c) => current.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if quote.is_some() {
+        return Err(format!("unclosed quote in --ffmpeg-args {}", args));
+    }
+    split_args.extend(current.take());
+    return Ok(split_args);
+}
+
+// one output video, from a kept segment or an in/out range
+#[derive(Debug)]
+pub struct Cut {
+    pub index: usize, // nth segment or nth range, starting from 0
+    pub label: String,
+    pub start: Seconds,
+    pub end: Seconds,
+    pub outpath: PathBuf, // or a pattern like clip_%05d.png for image sequences
+}
+
+pub fn render_template(
+    template: &str,
+    stem: &str,
+    ext: &str,
+    index: usize,
+    label: &str,
+    start: Seconds,
+    end: Seconds,
+) -> Result<String, String> {
+    // supported placeholders:
+    //   {stem}       source filename without extension
+    //   {ext}        source extension
+    //   {index}      segment or range number, starting from 0
+    //   {label}      segment label or range name, possibly empty
+    //   {start_hms}  like 00h01m02.500s
+    //   {end_hms}
+    //
+    // and an optional zero-padded width, like {index:02} -> 07
+    let mut rendered = String::new();
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        rendered.push_str(&rest[..open]);
+        let close = open
+            + rest[open..]
+                .find('}')
+                .ok_or_else(|| format!("unclosed {{ in output template {}", template))?;
+        let placeholder = &rest[open + 1..close];
+        let (name, width) = match placeholder.split_once(':') {
+            None => (placeholder, 0),
+            Some((name, width)) => (
+                name,
+                width.parse::<usize>().map_err(|_| {
+                    format!(
+                        "bad width {{{}}} in output template {}",
+                        placeholder, template
+                    )
+                })?,
+            ),
+        };
+        let value = match name {
+            "stem" => sanitize_filename_part(stem),
+            "ext" => ext.to_string(),
+            "index" => index.to_string(),
+            "label" => sanitize_filename_part(label),
+            "start_hms" => format_secs_to_hms(start),
+            "end_hms" => format_secs_to_hms(end),
+            _ => {
+                return Err(format!(
+                    "unknown placeholder {{{}}} in output template {}\nexpected one of {{stem}} {{ext}} {{index}} {{label}} {{start_hms}} {{end_hms}}",
+                    name, template
+                ))
+            }
+        };
+        rendered.push_str(&format!("{:0>width$}", value, width = width));
+        rest = &rest[close + 1..];
+    }
+    rendered.push_str(rest);
+
+    if rendered.is_empty() || rendered == "." || rendered == ".." {
+        return Err(format!(
+            "output template {} makes an invalid filename {:?}",
+            template, rendered
+        ));
+    }
+    return Ok(rendered);
+}
+
+pub fn sanitize_filename_part(part: &str) -> String {
+    // user-typed labels could contain anything,
+    // so replace characters that are invalid in filenames on linux, macos, or windows
+    return part
+        .chars()
+        .map(|c| match c.is_control() || "/\\:*?\"<>|".contains(c) {
+            true => '_',
+            false => c,
+        })
+        .collect::<String>()
+        .trim()
+        .to_string();
+}
+
+fn format_secs_to_hms(seconds: Seconds) -> String {
+    // filename-friendly timestamp, since colons are not allowed on windows
+    let hours = (seconds / 3600.0).floor();
+    let minutes = ((seconds - hours * 3600.0) / 60.0).floor();
+    let remaining_secs = seconds - hours * 3600.0 - minutes * 60.0;
+    return format!("{:02}h{:02}m{:06.3}s", hours, minutes, remaining_secs);
+}
+
+pub fn check_collisions(outpaths: &[&Path], check_existing_files: bool) -> Result<(), String> {
+    // make sure no output overwrites another output, or an existing file,
+    // before any ffmpeg command runs
+    for (i, outpath) in outpaths.iter().enumerate() {
+        if outpaths[..i].contains(outpath) {
+            return Err(format!(
+                "multiple outputs would be written to {}\ntry an --output-template with {{index}} or {{label}}",
+                outpath.display()
+            ));
+        }
+        if check_existing_files && outpath.exists() {
+            return Err(format!(
+                "output {} already exists, not overwriting",
+                outpath.display()
+            ));
+        }
+    }
+    return Ok(());
+}
+
+pub fn output_dir_and_stem(
+    video_path: &str,
+    options: &ExportOptions,
+) -> Result<(PathBuf, String), String> {
+    // /a/b/c.mp4 -> (/a/b, c), unless there's an --output-dir
+    //
+    // in the app, the video path was already opened by ffprobe and ffmpeg,
+    // but library callers could pass anything, like "" or "/"
+    let filepath = PathBuf::from(video_path);
+    let no_filename = || {
+        format!(
+            "video path {:?} has no filename to name outputs after",
+            video_path
+        )
+    };
+    let outdir = match &options.output_dir {
+        Some(dir) => dir.clone(),
+        None => filepath.parent().ok_or_else(no_filename)?.to_path_buf(),
+    };
+    let stem = filepath
+        .file_stem()
+        .ok_or_else(no_filename)?
+        .to_string_lossy()
+        .to_string();
+    return Ok((outdir, stem));
+}
+
+pub fn collect_cuts(
+    video_path: &str,
+    duration_secs: Seconds,
+    markers: &[Seconds],
+    segments: &[Segment],
+    ranges: &[ClipRange],
+    options: &ExportOptions,
+) -> Result<Vec<Cut>, String> {
+    // one output video per kept segment (if any markers were made), and one per in/out range
+    //
+    // /a/b/c.mp4 becomes /a/b/c_0.mp4, /a/b/c_1.mp4, ...
+    // and ranges become /a/b/c_range1.mp4, /a/b/c_range2.mp4, ...
+    // unless there's a different --output-template or --output-dir
+    timeline::check_markers(markers, segments)?;
+    let filepath = PathBuf::from(video_path);
+    let (outdir, stem) = output_dir_and_stem(video_path, options)?;
+    let outdir = outdir.as_path();
+    let stem = stem.as_str();
+    let extension = match options.extension() {
+        Some(extension) => extension,
+        None => filepath
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or("mp4"), // like for URLs without an extension
+    };
+    let outpath = |filename: String| match options.mode {
+        ExportMode::Frames => outdir.join(image_sequence_pattern(&filename)),
+        _ => outdir.join(filename),
+    };
+
+    let mut cuts = Vec::<Cut>::new();
+
+    if markers.len() > 0 {
+        // bookend markers with implicit start and end timestamps
+        let timestamps = std::iter::once(0.)
+            .chain(markers.iter().copied())
+            .chain(std::iter::once(duration_secs))
+            .collect::<Vec<Seconds>>();
+        let template = options
+            .output_template
+            .as_deref()
+            .unwrap_or(DEFAULT_SEGMENT_TEMPLATE);
+
+        for (i, segment) in segments.iter().enumerate() {
+            let (start, end) = (timestamps[i], timestamps[i + 1]);
+            // discarded segments still count towards i,
+            // so output filenames stay the same whether or not other segments are kept
+            if !segment.kept {
+                log!("discarding from {} to {}", start, end);
+                continue;
+            }
+            let filename =
+                render_template(template, stem, extension, i, &segment.label, start, end)?;
+            cuts.push(Cut {
+                index: i,
+                label: segment.label.clone(),
+                start: start,
+                end: end,
+                outpath: outpath(filename),
+            });
+        }
+    }
+
+    // in/out ranges are independent of markers, and may overlap or leave gaps
+    let template = options
+        .output_template
+        .as_deref()
+        .unwrap_or(DEFAULT_RANGE_TEMPLATE);
+    for (i, range) in ranges.iter().enumerate() {
+        let filename = render_template(
+            template,
+            stem,
+            extension,
+            i,
+            &range.name,
+            range.start,
+            range.end,
+        )?;
+        cuts.push(Cut {
+            index: i,
+            label: range.name.clone(),
+            start: range.start,
+            end: range.end,
+            outpath: outpath(filename),
+        });
+    }
+
+    return Ok(cuts);
+}
+
+fn image_sequence_pattern(filename: &str) -> String {
+    // numbered pngs, like clip_00001.png, clip_00002.png, ...
+    // from the filename clip.png
+    let stem = Path::new(filename).file_stem().unwrap_or_default();
+    return format!("{}_%05d.png", stem.to_string_lossy());
+}
+
+fn first_image_path(pattern: &Path) -> PathBuf {
+    // clip_%05d.png -> clip_00001.png, the file that would collide first
+    let filename = pattern.file_name().unwrap_or_default().to_string_lossy();
+    return pattern.with_file_name(filename.replace("%05d", "00001"));
+}
+
+pub fn plan_jobs(
+    video_path: &str,
+    duration_secs: Seconds,
+    markers: &[Seconds],
+    segments: &[Segment],
+    ranges: &[ClipRange],
+    still_frames: &[Seconds],
+    options: &ExportOptions,
+) -> Result<Vec<Job>, String> {
+    // the whole export recipe: every ffmpeg command, grouped by output.
+    // all output filenames are figured out and checked before any ffmpeg command runs
+    let (outdir, stem) = output_dir_and_stem(video_path, options)?;
+    let concat_audio_path = match options.concat_audio {
+        true => Some(concat_audio_path(&outdir, &stem, options)),
+        false => None,
+    };
+    let cuts = collect_cuts(
+        video_path,
+        duration_secs,
+        markers,
+        segments,
+        ranges,
+        options,
+    )?;
+    let outpaths = match &concat_audio_path {
+        Some(outpath) => vec![outpath.clone()],
+        None => cuts
+            .iter()
+            .map(|cut| match options.mode {
+                ExportMode::Frames => first_image_path(&cut.outpath),
+                _ => cut.outpath.clone(),
+            })
+            .collect(),
+    };
+    check_collisions(
+        &outpaths
+            .iter()
+            .map(PathBuf::as_path)
+            .collect::<Vec<&Path>>(),
+        !options.dry_run,
+    )?;
+
+    // still frames were already saved during the session, unless this is a dry run
+    let still_frame_jobs = still_frames.iter().map(|timestamp| {
+        let outpath = still_frame_path(&outdir, &stem, *timestamp);
+        Job {
+            cmds: vec![still_frame_cmd(video_path, *timestamp, &outpath)],
+            outpath: outpath,
+            duration: 0.0,
+            temp_files: vec![],
+        }
+    });
+
+    let cut_jobs = match &concat_audio_path {
+        Some(outpath) if cuts.len() > 0 => vec![Job {
+            outpath: outpath.clone(),
+            duration: cuts.iter().map(|cut| cut.end - cut.start).sum(),
+            cmds: vec![concat_audio_cmd(video_path, &cuts, outpath, options)],
+            temp_files: vec![],
+        }],
+        Some(_) => vec![],
+        None => cuts
+            .iter()
+            .enumerate()
+            .map(|(job_number, cut)| Job {
+                outpath: cut.outpath.clone(),
+                duration: cut.end - cut.start,
+                cmds: export_cmds(video_path, cut, job_number, options),
+                temp_files: match options.mode {
+                    ExportMode::Gif => vec![palette_path(cut, job_number)],
+                    _ => vec![],
+                },
+            })
+            .collect(),
+    };
+
+    return Ok(still_frame_jobs.chain(cut_jobs).collect());
+}
+
+pub fn export_cmds(
+    video_path: &str,
+    cut: &Cut,
+    job_number: usize, // unique within one export, for naming temp files
+    options: &ExportOptions,
+) -> Vec<std::process::Command> {
+    // ffmpeg commands to make one output. usually one command, but gifs need two passes
+    log!("trimming from {} to {}", cut.start, cut.end);
+    return match options.mode {
+        ExportMode::Video => vec![trim_cmd(
+            video_path,
+            cut.start,
+            cut.end,
+            &cut.outpath,
+            options,
+        )],
+        ExportMode::Gif => gif_cmds(video_path, cut, job_number, options),
+        ExportMode::Webp => vec![webp_cmd(video_path, cut, options)],
+        ExportMode::Frames => vec![frames_cmd(video_path, cut, options)],
+        ExportMode::Audio => vec![audio_cmd(video_path, cut, options)],
+    };
+}
+
+fn trimmed_input_cmd(video_path: &str, start: Seconds, end: Seconds) -> std::process::Command {
+    let mut cmd = std::process::Command::new("ffmpeg");
+    cmd.arg("-ss")
+        .arg(format!("{:.3}", start))
+        .arg("-to")
+        .arg(format!("{:.3}", end))
+        .arg("-i")
+        .arg(video_path);
+    return cmd;
+}
+
+pub fn trim_cmd(
+    video_path: &str,
+    start: Seconds,
+    end: Seconds,
+    outpath: &Path,
+    options: &ExportOptions,
+) -> std::process::Command {
+    let mut cmd = trimmed_input_cmd(video_path, start, end);
+    cmd
+        //
+        // TODO: confirm if -c copy uses millisecond-precision
+        // ie, will it ruin frame-perfect cuts?
+        // yes, i think it borks keyframes and ruins output videos
+        // .arg("-c")
+        // .arg("copy")
+        // (see Preset::SameAsSource)
+        .args(
+            options
+                .preset
+                .map_or("", |p| p.ffmpeg_args())
+                .split_whitespace(),
+        )
+        .args(&options.ffmpeg_args)
+        .arg(outpath);
+    return cmd;
+}
+
+fn palette_path(cut: &Cut, job_number: usize) -> PathBuf {
+    // a temp file for gif_cmds(), unique to this vic and job, so parallel exports
+    // and other vics don't overwrite it between the two passes.
+    // run_jobs() deletes it afterwards
+    return std::env::temp_dir().join(format!(
+        "vic_palette_{}_{}_{}.png",
+        std::process::id(),
+        job_number,
+        cut.outpath
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+    ));
+}
+
+fn gif_cmds(
+    video_path: &str,
+    cut: &Cut,
+    job_number: usize,
+    options: &ExportOptions,
+) -> Vec<std::process::Command> {
+    // gifs are limited to 256 colors, so ffmpeg's default palette looks pretty bad.
+    // instead, generate an optimal palette for this clip first, then use it.
+    // https://blog.pkh.me/p/21-high-quality-gif-with-ffmpeg.html
+    let palette_path = palette_path(cut, job_number);
+    let filters = format!(
+        "fps={},scale={}:-1:flags=lanczos",
+        options.fps, options.width_px
+    );
+
+    let mut palette_cmd = trimmed_input_cmd(video_path, cut.start, cut.end);
+    palette_cmd
+        .args(["-vf", &format!("{},palettegen=stats_mode=diff", filters)])
+        .arg("-y") // overwrite stale palettes from earlier runs
+        .arg(&palette_path);
+
+    let mut gif_cmd = trimmed_input_cmd(video_path, cut.start, cut.end);
+    gif_cmd
+        .arg("-i")
+        .arg(&palette_path)
+        .args([
+            "-lavfi",
+            &format!(
+                "{} [x]; [x][1:v] paletteuse=dither=bayer:bayer_scale=5:diff_mode=rectangle",
+                filters
+            ),
+        ])
+        .args(["-loop", "0"])
+        .args(&options.ffmpeg_args)
+        .arg(&cut.outpath);
+
+    return vec![palette_cmd, gif_cmd];
+}
+
+fn frames_cmd(video_path: &str, cut: &Cut, options: &ExportOptions) -> std::process::Command {
+    // cut.outpath is already a numbered pattern, see image_sequence_pattern()
+    let mut cmd = trimmed_input_cmd(video_path, cut.start, cut.end);
+    cmd.args([
+        "-vf",
+        // comma is escaped because it would otherwise separate filters
+        &format!("select=not(mod(n\\,{}))", options.every_nth_frame),
+    ])
+    // only output selected frames, rather than duplicating them to keep a constant framerate
+    .args(["-vsync", "vfr"])
+    .args(&options.ffmpeg_args)
+    .arg(&cut.outpath);
+    return cmd;
+}
+
+fn audio_cmd(video_path: &str, cut: &Cut, options: &ExportOptions) -> std::process::Command {
+    let mut cmd = trimmed_input_cmd(video_path, cut.start, cut.end);
+    cmd.arg("-vn")
+        .args(options.audio_format.ffmpeg_args().split_whitespace())
+        .args(&options.ffmpeg_args)
+        .arg(&cut.outpath);
+    return cmd;
+}
+
+pub fn concat_audio_path(outdir: &Path, stem: &str, options: &ExportOptions) -> PathBuf {
+    // like /a/b/video_audio.flac
+    return outdir.join(format!(
+        "{}_audio.{}",
+        sanitize_filename_part(stem),
+        options.audio_format.extension()
+    ));
+}
+
+pub fn concat_audio_cmd(
+    video_path: &str,
+    cuts: &[Cut],
+    outpath: &Path,
+    options: &ExportOptions,
+) -> std::process::Command {
+    // one trimmed input per cut, then join all their audio streams end to end:
+    // ffmpeg -ss 0 -to 5 -i a.mp4 -ss 9 -to 12 -i a.mp4 -filter_complex '[0:a][1:a]concat=n=2:v=0:a=1[out]' -map '[out]' a_audio.flac
+    let mut cmd = std::process::Command::new("ffmpeg");
+    for cut in cuts {
+        log!("joining audio from {} to {}", cut.start, cut.end);
+        cmd.args(["-ss", &format!("{:.3}", cut.start)])
+            .args(["-to", &format!("{:.3}", cut.end)])
+            .arg("-i")
+            .arg(video_path);
+    }
+    let inputs = (0..cuts.len())
+        .map(|i| format!("[{}:a]", i))
+        .collect::<String>();
+    cmd.args([
+        "-filter_complex",
+        &format!("{}concat=n={}:v=0:a=1[out]", inputs, cuts.len()),
+    ])
+    .args(["-map", "[out]"])
+    .args(options.audio_format.ffmpeg_args().split_whitespace())
+    .args(&options.ffmpeg_args)
+    .arg(outpath);
+    return cmd;
+}
+
+pub fn still_frame_path(outdir: &Path, stem: &str, timestamp: Seconds) -> PathBuf {
+    // like /a/b/video_00h01m02.500s.png
+    return outdir.join(format!(
+        "{}_{}.png",
+        sanitize_filename_part(stem),
+        format_secs_to_hms(timestamp)
+    ));
+}
+
+pub fn still_frame_cmd(
+    video_path: &str,
+    timestamp: Seconds,
+    outpath: &Path,
+) -> std::process::Command {
+    // one frame at full resolution.
+    // -accurate_seek is ffmpeg's default, but be explicit since exact frames matter here
+    let mut cmd = std::process::Command::new("ffmpeg");
+    cmd.args(["-ss", &format!("{:.3}", timestamp)])
+        .arg("-accurate_seek")
+        .arg("-i")
+        .arg(video_path)
+        .args(["-frames:v", "1"])
+        .arg(outpath);
+    return cmd;
+}
+
+fn webp_cmd(video_path: &str, cut: &Cut, options: &ExportOptions) -> std::process::Command {
+    // animated webp has full color, so no palette tricks needed like for gifs
+    let mut cmd = trimmed_input_cmd(video_path, cut.start, cut.end);
+    cmd.args([
+        "-vf",
+        &format!(
+            "fps={},scale={}:-1:flags=lanczos",
+            options.fps, options.width_px
+        ),
+    ])
+    .args(["-c:v", "libwebp", "-quality", "75", "-loop", "0", "-an"])
+    .args(&options.ffmpeg_args)
+    .arg(&cut.outpath);
+    return cmd;
+}
+
+#[cfg(test)]
+#[test]
+fn test_rendering_output_template() {
+    let rendered = render_template(
+        "{stem}_{index:02}_{label}_{start_hms}.{ext}",
+        "my video",
+        "mp4",
+        3,
+        "a/b: c?",
+        3723.5,
+        3800.0,
+    );
+    assert_eq!(
+        rendered,
+        Ok("my video_03_a_b_ c__01h02m03.500s.mp4".to_string())
+    );
+    assert!(render_template("{stem}_{nope}.{ext}", "a", "mp4", 0, "", 0.0, 1.0).is_err());
+    assert!(render_template("{stem", "a", "mp4", 0, "", 0.0, 1.0).is_err());
+    assert!(render_template("{label}", "a", "mp4", 0, "", 0.0, 1.0).is_err());
+}
+
+#[cfg(test)]
+#[test]
+fn test_splitting_ffmpeg_args_with_quotes() {
+    assert_eq!(
+        split_ffmpeg_args(r#"-vf "scale=640:-2, fps=24"  -metadata title='' -an"#),
+        Ok(vec![
+            "-vf".to_string(),
+            "scale=640:-2, fps=24".to_string(),
+            "-metadata".to_string(),
+            "title=".to_string(),
+            "-an".to_string(),
+        ])
+    );
+    assert_eq!(split_ffmpeg_args("  "), Ok(vec![]));
+    assert!(split_ffmpeg_args("-vf 'scale").is_err());
+}
+
+#[cfg(test)]
+#[test]
+fn test_gif_export_generates_then_uses_palette() {
+    let options = ExportOptions {
+        dry_run: true,
+        preset: Some(Preset::WebH264), // ignored for gifs
+        mode: ExportMode::Gif,
+        width_px: 320,
+        fps: 10,
+        ..Default::default()
+    };
+    let cut = Cut {
+        index: 0,
+        label: "".to_string(),
+        start: 1.0,
+        end: 2.0,
+        outpath: PathBuf::from("clip_0.gif"),
+    };
+    let cmds = export_cmds("video.mp4", &cut, 3, &options);
+    assert_eq!(cmds.len(), 2);
+    let palette = cmds[0].get_args().last().unwrap();
+    assert!(cmds[1].get_args().any(|arg| arg == palette));
+    assert_eq!(palette, palette_path(&cut, 3).as_os_str());
+    assert_ne!(palette, palette_path(&cut, 4).as_os_str()); // another job in the same export
+    assert!(!cmds[1].get_args().any(|arg| arg == "libx264"));
+    assert_eq!(options.extension(), Some("gif"));
+}
+
+#[cfg(test)]
+#[test]
+fn test_audio_export_per_cut_or_concatenated() {
+    let options = ExportOptions {
+        dry_run: true,
+        mode: ExportMode::Audio,
+        audio_format: AudioFormat::Mp3,
+        ..Default::default()
+    };
+    let segments = [true, false, true].map(|kept| Segment {
+        kept: kept,
+        label: "".to_string(),
+    });
+    let plan = |options: &ExportOptions| {
+        plan_jobs(
+            "dir/video.mp4",
+            10.0,
+            &[4.0, 6.0],
+            &segments,
+            &[],
+            &[],
+            options,
+        )
+        .unwrap()
+    };
+    let args = |cmd: &std::process::Command| {
+        cmd.get_args()
+            .map(|arg| arg.to_string_lossy().to_string())
+            .collect::<Vec<String>>()
+    };
+
+    let jobs = plan(&options);
+    assert_eq!(jobs.len(), 2);
+    assert_eq!(jobs[0].outpath, PathBuf::from("dir/video_0.mp3"));
+    assert!(args(&jobs[0].cmds[0]).contains(&"-vn".to_string()));
+    assert!(args(&jobs[0].cmds[0]).contains(&"libmp3lame".to_string()));
+
+    // both kept segments end up in one file, one after the other
+    let options = ExportOptions {
+        concat_audio: true,
+        ..options
+    };
+    let jobs = plan(&options);
+    assert_eq!(jobs.len(), 1);
+    assert_eq!(jobs[0].outpath, PathBuf::from("dir/video_audio.mp3"));
+    assert_eq!(jobs[0].duration, 8.0);
+    let args = args(&jobs[0].cmds[0]);
+    assert_eq!(args.iter().filter(|arg| *arg == "-i").count(), 2);
+    assert!(args.contains(&"[0:a][1:a]concat=n=2:v=0:a=1[out]".to_string()));
+    assert_eq!(args.last().unwrap(), "dir/video_audio.mp3");
+}
+
+#[cfg(test)]
+#[test]
+fn test_planning_jobs_for_kept_segments_and_ranges() {
+    let options = ExportOptions {
+        dry_run: true,
+        ..Default::default()
+    };
+    let segment = |kept: bool| Segment {
+        kept: kept,
+        label: "".to_string(),
+    };
+    let range = ClipRange {
+        name: "range1".to_string(),
+        start: 2.0,
+        end: 3.0,
+    };
+    let jobs = plan_jobs(
+        "dir/video.mkv",
+        10.0,
+        &[4.0, 6.0],
+        &[segment(true), segment(false), segment(true)],
+        &[range.clone()],
+        &[],
+        &options,
+    )
+    .unwrap();
+    let outpaths = jobs
+        .iter()
+        .map(|job| job.outpath.clone())
+        .collect::<Vec<PathBuf>>();
+    assert_eq!(
+        outpaths,
+        ["dir/video_0.mkv", "dir/video_2.mkv", "dir/video_range1.mkv"].map(PathBuf::from)
+    );
+    assert_eq!(
+        jobs.iter()
+            .map(|job| job.duration)
+            .collect::<Vec<Seconds>>(),
+        vec![4.0, 4.0, 1.0]
+    );
+
+    // library callers could pass a path with nothing to name outputs after,
+    // or markers and segments that don't line up
+    for video_path in ["", "/"] {
+        let cuts = collect_cuts(video_path, 10.0, &[], &[segment(true)], &[], &options);
+        assert!(cuts.is_err());
+    }
+    let plan = |markers: &[Seconds], segments: &[Segment]| {
+        plan_jobs("video.mkv", 10.0, markers, segments, &[], &[], &options)
+    };
+    assert!(plan(&[4.0], &[segment(true), segment(true)]).is_ok());
+    assert!(plan(&[4.0], &[segment(true)]).is_err());
+    assert!(plan(&[6.0, 4.0], &[segment(true), segment(true), segment(true)]).is_err());
+    assert!(plan(&[f64::NAN], &[segment(true), segment(true)]).is_err());
+}
+
+#[cfg(test)]
+#[test]
+fn test_image_sequences_dont_overwrite_existing_images() {
+    let outdir = std::env::temp_dir().join(format!("vic_test_frames_{}", std::process::id()));
+    std::fs::create_dir_all(&outdir).unwrap();
+    let options = ExportOptions {
+        output_dir: Some(outdir.clone()),
+        mode: ExportMode::Frames,
+        ..Default::default()
+    };
+    let segments = [true, true].map(|kept| Segment {
+        kept: kept,
+        label: "".to_string(),
+    });
+    let plan = || plan_jobs("video.mp4", 10.0, &[4.0], &segments, &[], &[], &options);
+
+    let jobs = plan().unwrap();
+    assert_eq!(jobs[1].outpath, outdir.join("video_1_%05d.png"));
+    assert_eq!(jobs[1].cmds[0].get_args().last().unwrap(), jobs[1].outpath);
+
+    std::fs::write(outdir.join("video_1_00001.png"), "").unwrap();
+    match plan() {
+        Ok(_) => panic!("expected the existing image to collide"),
+        Err(msg) => assert!(msg.contains("video_1_00001.png already exists")),
+    }
+    std::fs::remove_dir_all(&outdir).unwrap();
+}
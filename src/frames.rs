@@ -0,0 +1,871 @@
+// decoded video frames, as terminal graphics
+//
+// ffmpeg decodes video frames to pixel data on stdout (or another FrameSource makes them)
+// chafa converts pixel frames into terminal-encoded ANSI/CSI/block/unicode/ascii graphics
+
+use std::io::{BufRead, Read};
+
+use crate::error::VicError;
+use crate::probe::{get_ffprobe_video_metadata, VideoMetadata};
+use crate::{log, Columns, Logs, Rows, Seconds};
+
+pub const DOWNSCALE_FACTOR: f64 = 0.5; // 0.125;
+const NUM_COLOR_CHANNELS: i32 = 3;
+const NUM_DECODER_ERROR_LINES_TO_KEEP: usize = 20;
+
+// which symbols chafa draws frames with
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SymbolSet {
+    Default, // blocks, geometric shapes, and borders
+    Blocky,  // just half blocks, like --blocky
+    All,     // everything chafa has, prettiest but slowest
+}
+
+impl std::str::FromStr for SymbolSet {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "default" => Ok(SymbolSet::Default),
+            "blocky" => Ok(SymbolSet::Blocky),
+            "all" => Ok(SymbolSet::All),
+            _ => Err(format!(
+                "unknown symbol set {}\nexpected one of default, blocky, all",
+                s
+            )),
+        }
+    }
+}
+
+impl SymbolSet {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SymbolSet::Default => "default",
+            SymbolSet::Blocky => "blocky",
+            SymbolSet::All => "all",
+        }
+    }
+}
+
+// how many colors chafa draws frames with, for terminals without truecolor
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RenderMode {
+    Truecolor,
+    Indexed256,
+    Indexed16,
+}
+
+impl std::str::FromStr for RenderMode {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "truecolor" => Ok(RenderMode::Truecolor),
+            "256" => Ok(RenderMode::Indexed256),
+            "16" => Ok(RenderMode::Indexed16),
+            _ => Err(format!(
+                "unknown render mode {}\nexpected one of truecolor, 256, 16",
+                s
+            )),
+        }
+    }
+}
+
+impl RenderMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            RenderMode::Truecolor => "truecolor",
+            RenderMode::Indexed256 => "256",
+            RenderMode::Indexed16 => "16",
+        }
+    }
+}
+
+// --- FRAME SOURCES --- //
+//
+// where frames come from, before chafa draws them:
+//
+//   FfmpegSource    .. a video file, decoded by ffmpeg on $PATH. what vic normally uses
+//   SyntheticSource .. generated test patterns, no files or ffmpeg needed
+//   ImageDirSource  .. a directory of .ppm images, one per frame
+//
+// frames are rgb24 pixels, 3 bytes per pixel, row by row
+
+pub trait FrameSource {
+    // like fps and duration, which the app uses to count frames and timestamps
+    fn metadata(&self) -> &VideoMetadata;
+
+    // (width, height) in pixels of each frame from next_frame(),
+    // which can be smaller than the video
+    fn frame_size(&self) -> (i32, i32);
+
+    // so the next frame is the one at timestamp
+    fn seek(&mut self, timestamp: Seconds) -> Result<(), VicError>;
+
+    // None at the end of the video or after an error
+    fn next_frame(&mut self) -> Option<&[u8]>;
+
+    // like next_frame(), for sources that can skip a frame without producing its pixels
+    fn skip_frame(&mut self) -> bool {
+        return self.next_frame().is_some();
+    }
+
+    // latest error since the last seek, to show in the UI
+    fn last_error(&self) -> Option<String> {
+        return None;
+    }
+
+    // release anything still running, like a decoding process
+    fn stop(&mut self) {}
+}
+
+type DecoderErrors = std::sync::Arc<std::sync::Mutex<std::collections::VecDeque<String>>>;
+type OnDecoderOutput = std::sync::Arc<dyn Fn() + Send + Sync>;
+
+// one ffmpeg decoding process, which gets killed and reaped when dropped,
+// so replacing it on each seek never leaves old decoders running or as zombies
+struct Decoder {
+    process: std::process::Child,
+    stdout: std::process::ChildStdout, // pixels get piped to here
+    errors: DecoderErrors,             // latest lines of stderr, filled from a background thread
+}
+
+pub struct FfmpegSource {
+    pub video_path: String, // ideally path: P or &str, but String is just easier
+    metadata: VideoMetadata,
+    width_px: i32,  // downscaled
+    height_px: i32, // downscaled
+    decoder: Decoder,
+    on_decoder_output: OnDecoderOutput, // like to redraw when the decoder reports an error
+    pixel_buffer: Vec<u8>,
+}
+
+impl FfmpegSource {
+    pub fn open(
+        video_filepath: &str,
+        on_decoder_output: impl Fn() + Send + Sync + 'static,
+    ) -> Result<Self, VicError> {
+        // on_decoder_output() gets called after each new line of ffmpeg stderr, from another thread
+        let metadata = get_ffprobe_video_metadata(video_filepath)?;
+        let width_px = (metadata.width_px as f64 * DOWNSCALE_FACTOR) as i32;
+        let height_px = (metadata.height_px as f64 * DOWNSCALE_FACTOR) as i32;
+        let num_bytes =
+            frame_num_bytes(width_px, height_px).ok_or(VicError::UnsupportedStream(format!(
+                "{} has a bad size, {}x{}",
+                video_filepath, width_px, height_px
+            )))?;
+        let on_decoder_output: OnDecoderOutput = std::sync::Arc::new(on_decoder_output);
+        let decoder =
+            FfmpegSource::_create_decoding_process(video_filepath, 0.0, &on_decoder_output)?;
+
+        return Ok(Self {
+            video_path: video_filepath.to_string(),
+            metadata: metadata,
+            width_px: width_px,
+            height_px: height_px,
+            decoder: decoder,
+            on_decoder_output: on_decoder_output,
+            pixel_buffer: vec![0u8; num_bytes],
+        });
+    }
+
+    fn _create_decoding_process(
+        video_filepath: &str,
+        start_time: Seconds,
+        on_decoder_output: &OnDecoderOutput,
+    ) -> Result<Decoder, VicError> {
+        // init long-running ffmpeg decoding process.
+        // this is where a lot of the heavy lifting happens.
+        // ffmpeg must be available on $PATH.
+        // tested with ffmpeg version 3.4.8-ubuntu... built with gcc 7
+
+        let mut cmd = std::process::Command::new("ffmpeg");
+        cmd
+            // only errors on stderr, no banner or progress stats
+            .args(["-hide_banner", "-nostats", "-loglevel", "error"])
+            .args(["-ss", &format!("{:0<3}", start_time)])
+            .args(["-i", &video_filepath])
+            // .args(["-nostdin"]) -nostdin perhaps solves: https://stackoverflow.com/a/47114881
+            //
+            // rgb24 = 8:8:8 bytes, r:g:b
+            // this is a straightforward format, and chafa accepts it, so let's just always use it
+            .args(["-pix_fmt", "rgb24"])
+            .args(["-f", "rawvideo"])
+            // downscaling the video vastly improves performance
+            .args([
+                "-vf", // aka "-filter_complex",
+                &format!("scale=iw*{}:ih*{}", DOWNSCALE_FACTOR, DOWNSCALE_FACTOR),
+            ])
+            //
+            // maybe also try the arg max_muxing_queue_size='9999' ?
+            //
+            .args(["pipe:"])
+            .stdout(std::process::Stdio::piped())
+            //
+            // problem with .stderr(std::process::Stdio::piped()), if I don't consume it:
+            // stderr will eventually reach pipe capacity if not consumed,
+            // so the program will hang after ~270 secs or ~65536 bytes of ffmpeg stderr output.
+            //
+            // to read more descriptions of the problem:
+            // 0) https://wonger.dev/posts/chafa-ffmpeg-progress#ffmpeg-recipes
+            // 1) https://github.com/rust-lang/rust/issues/45572#issuecomment-860134955
+            // 2) https://github.com/oconnor663/duct.py/blob/master/gotchas.md#using-io-threads-to-avoid-blocking-children
+            // 3) https://docs.python.org/2/library/subprocess.html#subprocess.call#:~:text=not%20use%20stdout%3DPIPE%20or%20stderr%3DPIPE%20with%20this%20function%20as%20that%20can%20deadlock%20based%20on%20the%20child%20process%20output%20volume.
+            //
+            // Solutions:
+            // - use a lib like `duct` that uses background threads to prevent stdout and stderr
+            // from reaching pipe capacity,
+            // - copy that solution of threading into my program, or
+            // - redirect stderr to /dev/null, so it will always be consumed and never reach
+            // pipe capacity
+            //
+            // We used /dev/null for a while, but then decoding errors were invisible.
+            // Now a background thread drains stderr (see below),
+            // and -loglevel error keeps ffmpeg's usual line-by-line logs out of it.
+            //
+            .stderr(std::process::Stdio::piped());
+
+        let on_decoder_output = std::sync::Arc::clone(on_decoder_output);
+        let decoder = Decoder::spawn(&mut cmd, move || on_decoder_output())?;
+        log!("{}", "created ffmpeg decoding process");
+        return Ok(decoder);
+    }
+}
+
+impl FrameSource for FfmpegSource {
+    fn metadata(&self) -> &VideoMetadata {
+        return &self.metadata;
+    }
+
+    fn frame_size(&self) -> (i32, i32) {
+        return (self.width_px, self.height_px);
+    }
+
+    fn seek(&mut self, timestamp: Seconds) -> Result<(), VicError> {
+        // Start new process at any position in video.
+        // This should be faster than reading far ahead in the old process,
+        // and this enables "backward seeking" too.
+        //
+        // The old process gets dropped here, which kills and reaps it.
+        self.decoder = FfmpegSource::_create_decoding_process(
+            &self.video_path,
+            timestamp,
+            &self.on_decoder_output,
+        )?;
+        return Ok(());
+    }
+
+    fn next_frame(&mut self) -> Option<&[u8]> {
+        // None once ffmpeg runs out of frames,
+        // at the end of the video or after a decoding error
+        if self
+            .decoder
+            .stdout
+            .read_exact(&mut self.pixel_buffer)
+            .is_err()
+        {
+            self.decoder.check_exit_status();
+            return None;
+        }
+        return Some(&self.pixel_buffer);
+    }
+
+    fn last_error(&self) -> Option<String> {
+        // from the current decoding process only, so seeking clears old errors
+        return self.decoder.errors.lock().unwrap().back().cloned();
+    }
+
+    fn stop(&mut self) {
+        // on finish, so ffmpeg doesn't idle in the background during exports
+        self.decoder.stop();
+    }
+}
+
+impl Decoder {
+    fn spawn(
+        cmd: &mut std::process::Command,
+        on_output: impl Fn() + Send + 'static,
+    ) -> Result<Self, VicError> {
+        // cmd should already pipe stdout and stderr.
+        // on_output() gets called after each new line of stderr
+        let program = cmd.get_program().to_string_lossy().to_string();
+        let mut process = cmd
+            .spawn()
+            .map_err(|e| VicError::from_spawn_error(&program, e))?;
+
+        let stdout = process.stdout.take().ok_or(VicError::DecodeFailed(
+            "failed to take stdout from decoding process".to_string(),
+        ))?;
+        let stderr = process.stderr.take().ok_or(VicError::DecodeFailed(
+            "failed to take stderr from decoding process".to_string(),
+        ))?;
+
+        // keep the last few lines for the UI. the thread ends when the process exits
+        let errors = DecoderErrors::default();
+        let shared_errors = std::sync::Arc::clone(&errors);
+        std::thread::spawn(move || {
+            for line in std::io::BufReader::new(stderr).lines().flatten() {
+                log!("ffmpeg decoder: {}", line);
+                let mut lines = shared_errors.lock().unwrap();
+                if lines.len() == NUM_DECODER_ERROR_LINES_TO_KEEP {
+                    lines.pop_front();
+                }
+                lines.push_back(line);
+                drop(lines);
+                on_output();
+            }
+        });
+
+        return Ok(Self {
+            process: process,
+            stdout: stdout,
+            errors: errors,
+        });
+    }
+
+    fn check_exit_status(&mut self) {
+        // after stdout ends. ffmpeg usually explains its errors on stderr,
+        // but it can also exit early without a word, like when it gets killed,
+        // so report a failed exit status too, if stderr had nothing to say.
+        // stdout just closed, so the process should be done exiting in a moment
+        for _ in 0..10 {
+            match self.process.try_wait() {
+                Ok(Some(status)) if !status.success() => {
+                    log!("ffmpeg decoder {}", status);
+                    let mut lines = self.errors.lock().unwrap();
+                    if lines.is_empty() {
+                        lines.push_back(format!("exited early with {}", status));
+                    }
+                    return;
+                }
+                Ok(Some(_)) => return, // the end of the video
+                Ok(None) => std::thread::sleep(std::time::Duration::from_millis(10)),
+                Err(e) => {
+                    log!("failed to check on ffmpeg decoder: {}", e);
+                    return;
+                }
+            }
+        }
+    }
+
+    fn stop(&mut self) {
+        // kill() fails if the process already exited, which is fine.
+        // wait() reaps it either way, so it doesn't linger as a zombie
+        self.process.kill();
+        self.process.wait();
+    }
+}
+
+impl Drop for Decoder {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+// color bars with a black line sweeping across them, one column per frame,
+// so each frame is a little different, and tests can tell which frame they got
+pub struct SyntheticSource {
+    metadata: VideoMetadata,
+    frame_number: u32, // of the next frame
+    pixel_buffer: Vec<u8>,
+}
+
+const COLOR_BARS: [[u8; 3]; 7] = [
+    [255, 255, 255], // white
+    [255, 255, 0],   // yellow
+    [0, 255, 255],   // cyan
+    [0, 255, 0],     // green
+    [255, 0, 255],   // magenta
+    [255, 0, 0],     // red
+    [0, 0, 255],     // blue
+];
+
+impl SyntheticSource {
+    pub fn new(
+        width_px: i32,
+        height_px: i32,
+        fps: f64,
+        duration_secs: Seconds,
+    ) -> Result<Self, VicError> {
+        // the color bars are split by width, and frames are counted by fps
+        let num_bytes = match frame_num_bytes(width_px, height_px) {
+            Some(num_bytes) if num_bytes > 0 && fps.is_finite() && fps > 0.0 => num_bytes,
+            _ => {
+                return Err(VicError::UnsupportedStream(format!(
+                    "can't make a {}x{} test pattern at {} fps",
+                    width_px, height_px, fps
+                )))
+            }
+        };
+        return Ok(Self {
+            metadata: VideoMetadata {
+                width_px: width_px,
+                height_px: height_px,
+                fps: fps,
+                seconds_per_frame: 1.0 / fps,
+                duration_secs: duration_secs,
+            },
+            frame_number: 0,
+            pixel_buffer: vec![0u8; num_bytes],
+        });
+    }
+
+    fn num_frames(&self) -> u32 {
+        return (self.metadata.duration_secs * self.metadata.fps).ceil() as u32;
+    }
+}
+
+impl FrameSource for SyntheticSource {
+    fn metadata(&self) -> &VideoMetadata {
+        return &self.metadata;
+    }
+
+    fn frame_size(&self) -> (i32, i32) {
+        return (self.metadata.width_px, self.metadata.height_px);
+    }
+
+    fn seek(&mut self, timestamp: Seconds) -> Result<(), VicError> {
+        self.frame_number = (timestamp.max(0.0) * self.metadata.fps).floor() as u32;
+        return Ok(());
+    }
+
+    fn next_frame(&mut self) -> Option<&[u8]> {
+        if self.frame_number >= self.num_frames() {
+            return None;
+        }
+        let width = self.metadata.width_px as usize;
+        let sweep_col = self.frame_number as usize % width;
+        for (i, pixel) in self.pixel_buffer.chunks_exact_mut(3).enumerate() {
+            let col = i % width;
+            let color = match col == sweep_col {
+                true => [0, 0, 0],
+                false => COLOR_BARS[col * COLOR_BARS.len() / width],
+            };
+            pixel.copy_from_slice(&color);
+        }
+        self.frame_number += 1;
+        return Some(&self.pixel_buffer);
+    }
+
+    fn skip_frame(&mut self) -> bool {
+        let has_frame = self.frame_number < self.num_frames();
+        if has_frame {
+            self.frame_number += 1;
+        }
+        return has_frame;
+    }
+}
+
+// one image per frame, in filename order, like the output of:
+//
+//   ffmpeg -i video.mp4 frames/%05d.ppm
+//
+// only binary ppm (P6), since it's trivial to read without an image library
+pub struct ImageDirSource {
+    image_paths: Vec<std::path::PathBuf>,
+    metadata: VideoMetadata,
+    index: usize, // of the next frame
+    error: Option<String>,
+    pixel_buffer: Vec<u8>,
+}
+
+impl ImageDirSource {
+    pub fn open(dir: &std::path::Path, fps: f64) -> Result<Self, VicError> {
+        // frames are counted by fps, like for SyntheticSource
+        if !fps.is_finite() || fps <= 0.0 {
+            return Err(VicError::UnsupportedStream(format!(
+                "can't play images at {} fps",
+                fps
+            )));
+        }
+        let mut image_paths = std::fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.extension()
+                    .map(|ext| ext.eq_ignore_ascii_case("ppm"))
+                    .unwrap_or(false)
+            })
+            .collect::<Vec<std::path::PathBuf>>();
+        image_paths.sort();
+
+        // every frame is assumed to be the same size as the first one
+        let first_path = image_paths
+            .first()
+            .ok_or(VicError::UnsupportedStream(format!(
+                "no .ppm images found in {}",
+                dir.display()
+            )))?;
+        let (width_px, height_px, _) = parse_ppm(&std::fs::read(first_path)?)
+            .map_err(|e| VicError::DecodeFailed(format!("{} {}", first_path.display(), e)))?;
+
+        return Ok(Self {
+            metadata: VideoMetadata {
+                width_px: width_px,
+                height_px: height_px,
+                fps: fps,
+                seconds_per_frame: 1.0 / fps,
+                duration_secs: image_paths.len() as Seconds / fps,
+            },
+            image_paths: image_paths,
+            index: 0,
+            error: None,
+            pixel_buffer: vec![],
+        });
+    }
+}
+
+impl FrameSource for ImageDirSource {
+    fn metadata(&self) -> &VideoMetadata {
+        return &self.metadata;
+    }
+
+    fn frame_size(&self) -> (i32, i32) {
+        return (self.metadata.width_px, self.metadata.height_px);
+    }
+
+    fn seek(&mut self, timestamp: Seconds) -> Result<(), VicError> {
+        self.index = (timestamp.max(0.0) * self.metadata.fps).floor() as usize;
+        self.error = None;
+        return Ok(());
+    }
+
+    fn next_frame(&mut self) -> Option<&[u8]> {
+        let path = self.image_paths.get(self.index)?;
+        self.index += 1;
+
+        let bytes = match std::fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                self.error = Some(format!("{} {}", path.display(), e));
+                return None;
+            }
+        };
+        let expected_size = (self.metadata.width_px, self.metadata.height_px);
+        match parse_ppm(&bytes) {
+            Ok((width_px, height_px, pixels)) if (width_px, height_px) == expected_size => {
+                self.pixel_buffer = pixels.to_vec();
+                return Some(&self.pixel_buffer);
+            }
+            Ok((width_px, height_px, _)) => {
+                self.error = Some(format!(
+                    "{} is {}x{}, but the first image is {}x{}",
+                    path.display(),
+                    width_px,
+                    height_px,
+                    expected_size.0,
+                    expected_size.1
+                ));
+                return None;
+            }
+            Err(e) => {
+                self.error = Some(format!("{} {}", path.display(), e));
+                return None;
+            }
+        }
+    }
+
+    fn skip_frame(&mut self) -> bool {
+        // without reading the image
+        let has_frame = self.index < self.image_paths.len();
+        if has_frame {
+            self.index += 1;
+        }
+        return has_frame;
+    }
+
+    fn last_error(&self) -> Option<String> {
+        return self.error.clone();
+    }
+}
+
+fn parse_ppm(bytes: &[u8]) -> Result<(i32, i32, &[u8]), String> {
+    // P6 header, then raw rgb bytes:
+    //
+    //   P6
+    //   # optional comments
+    //   <width> <height>
+    //   255
+    //   <width * height * 3 bytes>
+    //
+    // https://netpbm.sourceforge.net/doc/ppm.html
+    let mut fields = Vec::<String>::new();
+    let mut pos = 0;
+    while fields.len() < 4 {
+        match bytes.get(pos) {
+            None => return Err("is not a complete ppm image".to_string()),
+            Some(b'#') => {
+                while pos < bytes.len() && bytes[pos] != b'\n' {
+                    pos += 1;
+                }
+            }
+            Some(byte) if byte.is_ascii_whitespace() => pos += 1,
+            Some(_) => {
+                let start = pos;
+                while pos < bytes.len() && !bytes[pos].is_ascii_whitespace() {
+                    pos += 1;
+                }
+                fields.push(String::from_utf8_lossy(&bytes[start..pos]).to_string());
+            }
+        }
+    }
+    pos += 1; // exactly one whitespace byte between the header and the pixels
+
+    if fields[0] != "P6" {
+        return Err("is not a binary ppm image (P6)".to_string());
+    }
+    let parse_field = |field: &str| {
+        field
+            .parse::<i32>()
+            .map_err(|e| format!("has a bad ppm header. {} {}", field, e))
+    };
+    let width_px = parse_field(&fields[1])?;
+    let height_px = parse_field(&fields[2])?;
+    if width_px <= 0 || height_px <= 0 {
+        return Err(format!("has a bad ppm size {}x{}", width_px, height_px));
+    }
+    if fields[3] != "255" {
+        return Err("is not an 8-bit ppm image (max value 255)".to_string());
+    }
+    // a made-up header can claim any size, so don't trust it to fit in memory
+    let num_bytes = frame_num_bytes(width_px, height_px)
+        .ok_or(format!("is too big, {}x{}", width_px, height_px))?;
+    return match bytes.get(pos..).and_then(|rest| rest.get(..num_bytes)) {
+        Some(pixels) => Ok((width_px, height_px, pixels)),
+        None => Err("is missing pixels".to_string()),
+    };
+}
+
+fn frame_num_bytes(width_px: i32, height_px: i32) -> Option<usize> {
+    // of rgb pixels, or None for a negative size or one too big for any buffer
+    let width_px = usize::try_from(width_px).ok()?;
+    let height_px = usize::try_from(height_px).ok()?;
+    return width_px
+        .checked_mul(height_px)
+        .and_then(|num_pixels| num_pixels.checked_mul(NUM_COLOR_CHANNELS as usize))
+        .filter(|num_bytes| *num_bytes <= isize::MAX as usize);
+}
+
+// --- RENDERING --- //
+
+// draws frames from any FrameSource as terminal graphics
+pub struct FrameIterator {
+    canvas: chafa::Canvas,
+    source: Box<dyn FrameSource>,
+    pub output_cols: Columns,     // aka chars
+    pub output_rows: Rows,        // aka lines
+    pub num_frames_rendered: u32, // for debugging
+}
+
+impl FrameIterator {
+    pub fn new(
+        source: Box<dyn FrameSource>,
+        output_cols: Columns,
+        output_rows: Rows,
+        symbols: SymbolSet,
+        render_mode: RenderMode,
+    ) -> Self {
+        // --- CHAFA CONFIG --- //
+
+        let symbol_map = chafa::SymbolMap::new();
+        symbol_map.add_by_tags(match symbols {
+            // SOLID = 1 symbol, full height block, which is ugly
+            // VHALF = 2 symbols, commonly used by other image2ansi libraries
+            // HALF = 4 symbols, horizontal or vertical half, just as ugly as SOLID
+            SymbolSet::Blocky => chafa::Symbols::VHALF,
+            //
+            // 29 + 11 + 97 ~= 140 symbols, fast enough and pretty enough
+            // TODO: maybe remove border symbols from this combo?
+            // since block + geometric looks decent,
+            // and there are a lot of border symbols (eg. noticeable performance impact)
+            SymbolSet::Default => {
+                chafa::Symbols::BLOCK | chafa::Symbols::GEOMETRIC | chafa::Symbols::BORDER
+            }
+            //
+            SymbolSet::All => chafa::Symbols::ALL, // ~600 symbols, very slow
+        });
+        let config = chafa::Config::new();
+        config.set_geometry(output_cols as i32, output_rows as i32);
+        config.set_symbol_map(symbol_map);
+        config.set_work_factor(1.0);
+        //
+        // TODO: check bindings to make sure chafa enums carry over properly
+        // the indexed canvas color modes haven't worked for me, but they're there to try
+        config.set_canvas_mode(match render_mode {
+            RenderMode::Truecolor => chafa::CanvasMode::TRUECOLOR,
+            RenderMode::Indexed256 => chafa::CanvasMode::INDEXED_256,
+            RenderMode::Indexed16 => chafa::CanvasMode::INDEXED_16,
+        });
+        let canvas = chafa::Canvas::new(config);
+
+        return Self {
+            canvas: canvas,
+            source: source,
+            output_cols: output_cols,
+            output_rows: output_rows,
+            num_frames_rendered: 0,
+        };
+    }
+
+    pub fn metadata(&self) -> &VideoMetadata {
+        return self.source.metadata();
+    }
+
+    pub fn take_frame(&mut self) -> Option<String> {
+        let (width_px, height_px) = self.source.frame_size();
+        let pixels = self.source.next_frame()?;
+        self.num_frames_rendered += 1;
+
+        self.canvas.draw_all_pixels(
+            chafa::PixelType::RGB8,
+            pixels,
+            width_px,
+            height_px,
+            (width_px * NUM_COLOR_CHANNELS) as i32,
+        );
+
+        let view_string = self.canvas.build_ansi();
+        return Some(view_string);
+    }
+
+    pub fn skip_some_frames(&mut self, num_frames: u32) -> Option<String> {
+        // When you only want to advance a few frames,
+        // without seeking (like spawning a new ffmpeg process),
+        // but also without calling chafa.draw each frame as you would in loop { take_frame() }
+        //
+        // If skipping many frames,
+        // you should probably just seek with .goto_timestamp()
+        for _ in 0..num_frames {
+            if !self.source.skip_frame() {
+                return None;
+            }
+        }
+        return self.take_frame();
+    }
+
+    pub fn goto_timestamp(&mut self, timestamp: Seconds) -> Result<Option<String>, VicError> {
+        self.source.seek(timestamp)?;
+        Ok(self.take_frame())
+    }
+
+    pub fn last_decoder_error(&self) -> Option<String> {
+        return self.source.last_error();
+    }
+
+    pub fn stop_decoder(&mut self) {
+        self.source.stop();
+    }
+}
+
+// seeking replaces the decoder many times in one session
+#[cfg(all(test, target_os = "linux"))]
+#[test]
+fn test_replacing_decoders_leaves_no_lingering_processes() {
+    let long_running_cmd = || {
+        let mut cmd = std::process::Command::new("sleep");
+        cmd.arg("60")
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped());
+        cmd
+    };
+
+    let mut decoder = Decoder::spawn(&mut long_running_cmd(), || ()).unwrap();
+    let mut pids = vec![decoder.process.id()];
+    for _ in 0..50 {
+        decoder = Decoder::spawn(&mut long_running_cmd(), || ()).unwrap();
+        pids.push(decoder.process.id());
+    }
+    drop(decoder);
+
+    // a zombie would still have a /proc entry, so this checks for those too
+    for pid in pids {
+        assert!(
+            !std::path::Path::new(&format!("/proc/{}", pid)).exists(),
+            "decoder process {} is still around",
+            pid
+        );
+    }
+}
+
+#[cfg(all(test, unix))]
+#[test]
+fn test_silent_decoder_failures_are_still_reported() {
+    let sh_cmd = |script: &str| {
+        let mut cmd = std::process::Command::new("sh");
+        cmd.args(["-c", script])
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped());
+        cmd
+    };
+    let last_error = |decoder: &Decoder| decoder.errors.lock().unwrap().back().cloned();
+
+    let mut decoder = Decoder::spawn(&mut sh_cmd("exit 3"), || ()).unwrap();
+    decoder.stdout.read_to_end(&mut vec![]).unwrap();
+    decoder.check_exit_status();
+    assert_eq!(
+        last_error(&decoder),
+        Some("exited early with exit status: 3".to_string())
+    );
+
+    // a clean exit is just the end of the video
+    let mut decoder = Decoder::spawn(&mut sh_cmd("exit 0"), || ()).unwrap();
+    decoder.stdout.read_to_end(&mut vec![]).unwrap();
+    decoder.check_exit_status();
+    assert_eq!(last_error(&decoder), None);
+}
+
+#[cfg(test)]
+#[test]
+fn test_synthetic_source_seeks_by_timestamp() {
+    // the sweeping black column is at frame_number % width
+    let mut source = SyntheticSource::new(8, 2, 4.0, 2.0).unwrap();
+    let black_col = |pixels: &[u8]| pixels[..8 * 3].chunks(3).position(|p| p == [0, 0, 0]);
+
+    assert_eq!(black_col(source.next_frame().unwrap()), Some(0));
+    source.seek(1.25).unwrap();
+    assert_eq!(black_col(source.next_frame().unwrap()), Some(5));
+    assert!(source.skip_frame());
+    assert_eq!(black_col(source.next_frame().unwrap()), Some(7));
+    assert!(source.next_frame().is_none());
+
+    assert!(SyntheticSource::new(0, 2, 4.0, 2.0).is_err());
+    assert!(SyntheticSource::new(8, 2, 0.0, 2.0).is_err());
+    assert!(SyntheticSource::new(8, 2, f64::INFINITY, 2.0).is_err());
+    assert!(SyntheticSource::new(i32::MAX, i32::MAX, 4.0, 2.0).is_err());
+}
+
+#[cfg(test)]
+#[test]
+fn test_image_dir_source_reads_ppm_frames_in_order() {
+    let dir = std::env::temp_dir().join(format!("vic_test_ppm_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    // written out of order, to check sorting. one 2x1 image per frame
+    for (name, shade) in [("002.ppm", 20u8), ("000.ppm", 0), ("001.ppm", 10)] {
+        let mut bytes = b"P6\n# made by vic\n2 1\n255\n".to_vec();
+        bytes.extend([shade; 6]);
+        std::fs::write(dir.join(name), bytes).unwrap();
+    }
+    std::fs::write(dir.join("notes.txt"), "not a frame").unwrap();
+
+    let mut source = ImageDirSource::open(&dir, 2.0).unwrap();
+    assert_eq!(source.frame_size(), (2, 1));
+    assert_eq!(source.metadata().duration_secs, 1.5);
+    assert_eq!(source.next_frame(), Some(&[0u8; 6][..]));
+    source.seek(1.0).unwrap();
+    assert_eq!(source.next_frame(), Some(&[20u8; 6][..]));
+    assert!(source.next_frame().is_none());
+    assert_eq!(source.last_error(), None);
+    for fps in [0.0, -1.0, f64::NAN] {
+        assert!(ImageDirSource::open(&dir, fps).is_err());
+    }
+
+    // bad headers are errors, not panics
+    for header in [
+        "P6\n-2 1\n255\n",
+        "P6\n0 1\n255\n",
+        "P6\n2147483647 2147483647\n255\n",
+    ] {
+        let mut bytes = header.as_bytes().to_vec();
+        bytes.extend([0u8; 6]);
+        assert!(parse_ppm(&bytes).is_err(), "{:?}", header);
+    }
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
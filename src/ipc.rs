@@ -0,0 +1,290 @@
+// --ipc: remote control over a unix socket, for editor plugins and scripts
+//
+// like mpv's json ipc, it's one json object per line, each way.
+// a request is a command array, plus an optional request_id that the reply echoes:
+//
+//   -> {"command": ["seek", 12.5], "request_id": 1}
+//   <- {"request_id":1,"error":"success","data":null}
+//   -> {"command": ["get-markers"]}
+//   <- {"error":"success","data":[5,20.5]}
+//   -> {"command": ["remove-marker", 7]}
+//   <- {"error":"no marker at 0:07","data":null}
+//
+// after ["subscribe"], a client also gets events when things change:
+//
+//   <- {"event":"markers","data":[5,20.5,31]}
+//
+// this file only moves lines around. the app decides what commands do,
+// one request at a time between frames, and which events to push (see app.rs)
+
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+
+use crate::error::VicError;
+use crate::json::{self, Value};
+use crate::log;
+
+// each client is written to from its own thread, so one that doesn't read its socket
+// can't freeze playback. it gets dropped once this many lines are waiting for it,
+// and its thread gives up once a write has waited this long
+const MAX_QUEUED_LINES: usize = 1000;
+const WRITE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(1);
+
+pub struct Request {
+    pub client_id: usize,
+    pub request_id: Option<Value>,
+    pub command: Vec<Value>, // like ["seek", 12.5]
+}
+
+struct Client {
+    id: usize,
+    lines: SyncSender<String>, // to its writer thread
+    writer: std::thread::JoinHandle<()>,
+    subscribed: bool,
+}
+
+// shared with the threads that read each client
+type Clients = Arc<Mutex<Vec<Client>>>;
+
+pub struct IpcServer {
+    path: PathBuf,
+    clients: Clients,
+    last_events: Vec<(String, Value)>, // so unchanged events aren't pushed again
+}
+
+impl IpcServer {
+    #[cfg(unix)]
+    pub fn start(
+        path: &Path,
+        on_request: impl Fn(Request) + Send + Sync + 'static,
+    ) -> Result<Self, VicError> {
+        // on_request() gets called from a background thread for each well-formed request.
+        // malformed ones are answered here, without bothering the app
+        use std::os::unix::fs::FileTypeExt;
+        use std::os::unix::net::UnixListener;
+
+        // a socket left behind by a crashed vic would make bind() fail,
+        // but don't delete anything that isn't a socket
+        if let Ok(file_metadata) = std::fs::symlink_metadata(path) {
+            if file_metadata.file_type().is_socket() {
+                std::fs::remove_file(path)?;
+            }
+        }
+        let listener = UnixListener::bind(path).map_err(|e| {
+            VicError::BadCliArg(format!(
+                "failed to listen on --ipc {}. {}",
+                path.display(),
+                e
+            ))
+        })?;
+        log!("listening for ipc clients on {}", path.display());
+
+        let clients = Clients::default();
+        let on_request = Arc::new(on_request);
+        std::thread::spawn({
+            let clients = Arc::clone(&clients);
+            move || {
+                for (id, stream) in listener.incoming().enumerate() {
+                    let stream = match stream {
+                        Ok(stream) => stream,
+                        Err(e) => {
+                            log!("failed to accept an ipc client: {}", e);
+                            continue;
+                        }
+                    };
+                    let writer = match stream.try_clone() {
+                        Ok(writer) => writer,
+                        Err(e) => continue,
+                    };
+                    writer.set_write_timeout(Some(WRITE_TIMEOUT));
+                    let (lines, queued_lines) = std::sync::mpsc::sync_channel(MAX_QUEUED_LINES);
+                    clients.lock().unwrap().push(Client {
+                        id: id,
+                        lines: lines,
+                        writer: std::thread::spawn(move || write_lines(id, writer, queued_lines)),
+                        subscribed: false,
+                    });
+                    log!("ipc client {} connected", id);
+
+                    let clients = Arc::clone(&clients);
+                    let on_request = Arc::clone(&on_request);
+                    std::thread::spawn(move || {
+                        read_requests(id, std::io::BufReader::new(stream), &clients, &*on_request)
+                    });
+                }
+            }
+        });
+
+        return Ok(Self {
+            path: path.to_path_buf(),
+            clients: clients,
+            last_events: vec![],
+        });
+    }
+
+    #[cfg(not(unix))]
+    pub fn start(
+        path: &Path,
+        on_request: impl Fn(Request) + Send + Sync + 'static,
+    ) -> Result<Self, VicError> {
+        return Err(VicError::BadCliArg(
+            "--ipc needs unix sockets, which this platform doesn't have".to_string(),
+        ));
+    }
+
+    pub fn respond(&self, request: &Request, result: Result<Value, String>) {
+        let (error, data) = match result {
+            Ok(data) => ("success".to_string(), data),
+            Err(msg) => (msg, Value::Null),
+        };
+        let mut reply = vec![("error", Value::Str(error)), ("data", data)];
+        if let Some(request_id) = &request.request_id {
+            reply.insert(0, ("request_id", request_id.clone()));
+        }
+        send_line(&self.clients, &Value::object(reply).to_string(), |client| {
+            client.id == request.client_id
+        });
+    }
+
+    pub fn subscribe(&self, client_id: usize) {
+        for client in self.clients.lock().unwrap().iter_mut() {
+            if client.id == client_id {
+                client.subscribed = true;
+            }
+        }
+    }
+
+    pub fn last_pushed(&self, event: &str) -> Option<&Value> {
+        return self
+            .last_events
+            .iter()
+            .find(|(name, _)| name == event)
+            .map(|(_, data)| data);
+    }
+
+    pub fn push(&mut self, event: &str, data: Value) {
+        // to subscribed clients, unless it's the same as last time
+        if self.last_pushed(event) == Some(&data) {
+            return;
+        }
+        let line = Value::object(vec![
+            ("event", Value::Str(event.to_string())),
+            ("data", data.clone()),
+        ])
+        .to_string();
+        send_line(&self.clients, &line, |client| client.subscribed);
+
+        self.last_events.retain(|(name, _)| name != event);
+        self.last_events.push((event.to_string(), data));
+    }
+}
+
+impl Drop for IpcServer {
+    fn drop(&mut self) {
+        // connected clients see the socket close when vic exits,
+        // but first they get whatever is still queued for them, like the finish event
+        std::fs::remove_file(&self.path);
+        let clients = std::mem::take(&mut *self.clients.lock().unwrap());
+        for client in clients {
+            drop(client.lines);
+            client.writer.join();
+        }
+    }
+}
+
+fn read_requests(
+    client_id: usize,
+    reader: impl BufRead,
+    clients: &Clients,
+    on_request: &(impl Fn(Request) + ?Sized),
+) {
+    // until the client hangs up
+    for line in reader.lines().map_while(Result::ok) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match parse_request(client_id, &line) {
+            Ok(request) => on_request(request),
+            Err(msg) => {
+                let reply = Value::object(vec![("error", Value::Str(msg)), ("data", Value::Null)]);
+                send_line(clients, &reply.to_string(), |client| client.id == client_id);
+            }
+        }
+    }
+    clients
+        .lock()
+        .unwrap()
+        .retain(|client| client.id != client_id);
+    log!("ipc client {} disconnected", client_id);
+}
+
+fn write_lines(client_id: usize, mut writer: impl Write, lines: Receiver<String>) {
+    // until the client is dropped, or stops being writable.
+    // then send_line() notices that nobody's receiving, and drops the client
+    for line in lines {
+        if let Err(e) = writeln!(writer, "{}", line).and_then(|_| writer.flush()) {
+            log!("failed to write to ipc client {}: {}", client_id, e);
+            return;
+        }
+    }
+}
+
+fn parse_request(client_id: usize, line: &str) -> Result<Request, String> {
+    let value = json::parse(line).map_err(|e| format!("bad json: {}", e))?;
+    let command = match value.get("command").and_then(|command| command.as_array()) {
+        Some(command) if !command.is_empty() => command.clone(),
+        _ => return Err("expected a request like {\"command\": [\"get-position\"]}".to_string()),
+    };
+    return Ok(Request {
+        client_id: client_id,
+        request_id: value.get("request_id").cloned(),
+        command: command,
+    });
+}
+
+fn send_line(clients: &Clients, line: &str, to: impl Fn(&Client) -> bool) {
+    // only queues the line, so this never waits on a client.
+    // clients that can't be written to anymore, or have fallen too far behind, are dropped
+    clients.lock().unwrap().retain(|client| {
+        if !to(client) {
+            return true;
+        }
+        match client.lines.try_send(line.to_string()) {
+            Ok(_) => true,
+            Err(TrySendError::Full(_)) => {
+                log!("dropped ipc client {}, which stopped reading", client.id);
+                false
+            }
+            Err(TrySendError::Disconnected(_)) => {
+                log!("dropped ipc client {}", client.id);
+                false
+            }
+        }
+    });
+}
+
+#[cfg(all(test, unix))]
+#[test]
+fn test_a_client_that_stops_reading_doesnt_hold_up_pushes() {
+    let path = std::env::temp_dir().join(format!("vic_test_ipc_slow_{}.sock", std::process::id()));
+    let mut server = IpcServer::start(&path, |_| ()).unwrap();
+    let client = std::os::unix::net::UnixStream::connect(&path).unwrap();
+    while server.clients.lock().unwrap().is_empty() {
+        std::thread::sleep(std::time::Duration::from_millis(1));
+    }
+    server.subscribe(0);
+
+    // far more than fits in the socket's buffer
+    let start = std::time::Instant::now();
+    for i in 0..2 * MAX_QUEUED_LINES {
+        server.push(
+            "position",
+            Value::Str(format!("{} {}", i, "x".repeat(1000))),
+        );
+    }
+    assert!(start.elapsed() < WRITE_TIMEOUT);
+    assert!(server.clients.lock().unwrap().is_empty());
+    drop(client);
+}
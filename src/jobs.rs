@@ -0,0 +1,314 @@
+// runs the export's ffmpeg commands, maybe in parallel, and shows their progress on stderr
+//
+// each job makes one output from one or more ffmpeg commands, run in order.
+// ffmpeg reports its progress on stdout with `-progress pipe:1`, as blocks of key=value lines:
+//
+//   frame=120
+//   out_time_us=5005000
+//   ...
+//   progress=continue
+//
+// and out_time_us compared to the job's duration is roughly the fraction done
+
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::{mpsc, Arc, Mutex};
+
+use crossterm::{cursor, queue, style::Print, terminal, tty::IsTty};
+
+use crate::{log, Logs, Seconds};
+
+const NUM_STDERR_LINES_TO_KEEP: usize = 5;
+const PROGRESS_BAR_WIDTH: usize = 30;
+
+pub struct Job {
+    pub outpath: PathBuf,
+    pub duration: Seconds, // of the output, to compare against ffmpeg's progress
+    pub cmds: Vec<Command>,
+    pub temp_files: Vec<PathBuf>, // like gif palettes, deleted once the job is done
+}
+
+#[derive(Debug)]
+pub struct Failure {
+    pub exit_code: Option<i32>, // None if ffmpeg didn't start, or was killed by a signal
+    pub last_stderr_lines: Vec<String>,
+}
+
+enum JobStatus {
+    Waiting,
+    Running(f64), // fraction done, 0 to 1
+    Done(Result<(), Failure>),
+}
+
+// sent from worker threads to the main thread
+enum JobUpdate {
+    Started(usize),
+    Progress(usize, f64),
+    Finished(usize, Result<(), Failure>),
+}
+
+pub fn run_jobs(jobs: Vec<Job>, num_workers: usize) -> Vec<(PathBuf, Result<(), Failure>)> {
+    let outpaths = jobs
+        .iter()
+        .map(|job| job.outpath.clone())
+        .collect::<Vec<PathBuf>>();
+    let mut statuses = jobs
+        .iter()
+        .map(|_| JobStatus::Waiting)
+        .collect::<Vec<JobStatus>>();
+
+    // workers take the next job off a shared queue until it's empty,
+    // so at most num_workers ffmpegs run at once
+    let queue = Arc::new(Mutex::new(
+        jobs.into_iter()
+            .enumerate()
+            .collect::<VecDeque<(usize, Job)>>(),
+    ));
+    let (tx, rx) = mpsc::channel::<JobUpdate>();
+    let workers = (0..num_workers.max(1))
+        .map(|_| {
+            let queue = Arc::clone(&queue);
+            let tx = tx.clone();
+            std::thread::spawn(move || loop {
+                let next_job = queue.lock().unwrap().pop_front();
+                let (i, job) = match next_job {
+                    Some(next_job) => next_job,
+                    None => break,
+                };
+                tx.send(JobUpdate::Started(i));
+                let result = run_job(i, &job, &tx);
+                for path in &job.temp_files {
+                    std::fs::remove_file(path);
+                }
+                tx.send(JobUpdate::Finished(i, result));
+            })
+        })
+        .collect::<Vec<std::thread::JoinHandle<()>>>();
+    drop(tx); // so rx stops iterating once every worker is done
+
+    let mut stderr = std::io::stderr();
+    let is_tty = stderr.is_tty();
+    let mut num_bar_lines = 0;
+    for update in rx {
+        match update {
+            JobUpdate::Started(i) => statuses[i] = JobStatus::Running(0.0),
+            JobUpdate::Progress(i, fraction) => statuses[i] = JobStatus::Running(fraction),
+            JobUpdate::Finished(i, result) => {
+                // finished jobs get a permanent line above the progress bars
+                clear_lines(&mut stderr, num_bar_lines, is_tty);
+                num_bar_lines = 0;
+                match &result {
+                    Ok(_) => queue!(
+                        stderr,
+                        Print(format!("  done    {}\n", outpaths[i].display()))
+                    ),
+                    Err(_) => queue!(
+                        stderr,
+                        Print(format!("  failed  {}\n", outpaths[i].display()))
+                    ),
+                };
+                statuses[i] = JobStatus::Done(result);
+            }
+        }
+        if is_tty {
+            clear_lines(&mut stderr, num_bar_lines, is_tty);
+            num_bar_lines = draw_progress_bars(&mut stderr, &statuses, &outpaths);
+        }
+        stderr.flush();
+    }
+    clear_lines(&mut stderr, num_bar_lines, is_tty);
+    stderr.flush();
+
+    for worker in workers {
+        worker.join();
+    }
+
+    return outpaths
+        .into_iter()
+        .zip(statuses)
+        .map(|(outpath, status)| match status {
+            JobStatus::Done(result) => (outpath, result),
+            // only if a worker thread panicked
+            _ => (
+                outpath,
+                Err(Failure {
+                    exit_code: None,
+                    last_stderr_lines: vec!["job never finished".to_string()],
+                }),
+            ),
+        })
+        .collect();
+}
+
+fn run_job(i: usize, job: &Job, tx: &mpsc::Sender<JobUpdate>) -> Result<(), Failure> {
+    let num_cmds = job.cmds.len();
+    for (n, cmd) in job.cmds.iter().enumerate() {
+        log!("running: {:?}", cmd);
+        let mut child = with_progress(cmd)
+            .stdin(Stdio::null()) // else parallel ffmpegs all fight over keypresses
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| Failure {
+                exit_code: None,
+                last_stderr_lines: vec![format!("failed to start ffmpeg: {}", e)],
+            })?;
+
+        // drain stderr on its own thread, else ffmpeg could block on a full pipe
+        let child_stderr = child.stderr.take().unwrap();
+        let stderr_reader = std::thread::spawn(move || {
+            let mut last_lines = VecDeque::new();
+            for line in BufReader::new(child_stderr).lines().flatten() {
+                if last_lines.len() == NUM_STDERR_LINES_TO_KEEP {
+                    last_lines.pop_front();
+                }
+                last_lines.push_back(line);
+            }
+            return Vec::from(last_lines);
+        });
+
+        let child_stdout = child.stdout.take().unwrap();
+        for line in BufReader::new(child_stdout).lines().flatten() {
+            // out_time_us may also be N/A, which just gets skipped
+            if let Some(micros) = line
+                .strip_prefix("out_time_us=")
+                .and_then(|s| s.parse::<f64>().ok())
+            {
+                let cmd_fraction = match job.duration > 0.0 {
+                    true => (micros / 1_000_000.0 / job.duration).clamp(0.0, 1.0),
+                    false => 0.0,
+                };
+                tx.send(JobUpdate::Progress(
+                    i,
+                    (n as f64 + cmd_fraction) / num_cmds as f64,
+                ));
+            }
+        }
+
+        let status = child.wait().map_err(|e| Failure {
+            exit_code: None,
+            last_stderr_lines: vec![format!("failed to wait for ffmpeg: {}", e)],
+        })?;
+        let last_stderr_lines = stderr_reader.join().unwrap_or_default();
+        if !status.success() {
+            log!("ffmpeg failed with {}", status);
+            return Err(Failure {
+                exit_code: status.code(),
+                last_stderr_lines: last_stderr_lines,
+            });
+        }
+    }
+    return Ok(());
+}
+
+fn with_progress(cmd: &Command) -> Command {
+    // -progress is a global option, so it can go first, before any inputs
+    let mut progress_cmd = Command::new(cmd.get_program());
+    progress_cmd
+        .args(["-hide_banner", "-nostats", "-progress", "pipe:1"])
+        .args(cmd.get_args());
+    return progress_cmd;
+}
+
+fn clear_lines(stderr: &mut std::io::Stderr, num_lines: usize, is_tty: bool) {
+    if is_tty && num_lines > 0 {
+        queue!(
+            stderr,
+            cursor::MoveToPreviousLine(num_lines as u16),
+            terminal::Clear(terminal::ClearType::FromCursorDown),
+        );
+    }
+}
+
+fn draw_progress_bars(
+    stderr: &mut std::io::Stderr,
+    statuses: &[JobStatus],
+    outpaths: &[PathBuf],
+) -> usize {
+    // one bar per running job, then one for all jobs:
+    //
+    //   [#########---------------------]  30% video_0.mp4
+    //   [##############----------------]  48% video_2.mp4
+    //   [#####-------------------------]  17% overall, 1 of 6 done
+    //
+    let mut num_lines = 0;
+    for (status, outpath) in statuses.iter().zip(outpaths) {
+        if let JobStatus::Running(fraction) = status {
+            let name = outpath.file_name().unwrap_or_default().to_string_lossy();
+            queue!(
+                stderr,
+                Print(format!("  {} {}\n", progress_bar(*fraction), name))
+            );
+            num_lines += 1;
+        }
+    }
+
+    let num_done = statuses
+        .iter()
+        .filter(|status| matches!(status, JobStatus::Done(_)))
+        .count();
+    let overall_fraction = statuses
+        .iter()
+        .map(|status| match status {
+            JobStatus::Waiting => 0.0,
+            JobStatus::Running(fraction) => *fraction,
+            JobStatus::Done(_) => 1.0,
+        })
+        .sum::<f64>()
+        / statuses.len().max(1) as f64;
+    queue!(
+        stderr,
+        Print(format!(
+            "  {} overall, {} of {} done\n",
+            progress_bar(overall_fraction),
+            num_done,
+            statuses.len()
+        ))
+    );
+    return num_lines + 1;
+}
+
+fn progress_bar(fraction: f64) -> String {
+    let num_filled = (fraction.clamp(0.0, 1.0) * PROGRESS_BAR_WIDTH as f64).round() as usize;
+    return format!(
+        "[{}{}] {:>3}%",
+        "#".repeat(num_filled),
+        "-".repeat(PROGRESS_BAR_WIDTH - num_filled),
+        (fraction.clamp(0.0, 1.0) * 100.0).round() as usize
+    );
+}
+
+pub fn print_summary(results: &[(PathBuf, Result<(), Failure>)]) {
+    let num_failed = results.iter().filter(|(_, result)| result.is_err()).count();
+    eprintln!(
+        "\n{} succeeded, {} failed",
+        results.len() - num_failed,
+        num_failed
+    );
+    for (outpath, result) in results {
+        if let Err(failure) = result {
+            let exit_code = match failure.exit_code {
+                Some(code) => format!("exit code {}", code),
+                None => "no exit code".to_string(),
+            };
+            eprintln!("\n  {} ({})", outpath.display(), exit_code);
+            for line in &failure.last_stderr_lines {
+                eprintln!("    {}", line);
+            }
+        }
+    }
+    eprintln!("");
+}
+
+#[cfg(test)]
+#[test]
+fn test_progress_bar_is_clamped() {
+    assert_eq!(
+        progress_bar(0.5),
+        format!("[{}{}]  50%", "#".repeat(15), "-".repeat(15))
+    );
+    assert_eq!(progress_bar(1.7), progress_bar(1.0));
+    assert_eq!(progress_bar(-0.2), progress_bar(0.0));
+}
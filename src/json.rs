@@ -0,0 +1,377 @@
+// just enough json for vic's own formats, without pulling in serde:
+// --dry-run=json recipes, and the --ipc protocol
+//
+// objects keep their keys in order, so output is stable and easy to diff
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    Str(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    pub fn object(fields: Vec<(&str, Value)>) -> Value {
+        return Value::Object(
+            fields
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        );
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        return match self {
+            Value::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        };
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        return match self {
+            Value::Number(n) => Some(*n),
+            _ => None,
+        };
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        return match self {
+            Value::Str(s) => Some(s),
+            _ => None,
+        };
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        return match self {
+            Value::Bool(b) => Some(*b),
+            _ => None,
+        };
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<Value>> {
+        return match self {
+            Value::Array(items) => Some(items),
+            _ => None,
+        };
+    }
+
+    pub fn pretty(&self) -> String {
+        // two-space indents, for people reading it.
+        // Display is the compact one-line version, for other programs
+        let mut text = String::new();
+        self.write_pretty(&mut text, 0);
+        return text;
+    }
+
+    fn write_pretty(&self, text: &mut String, depth: usize) {
+        let indent = |depth: usize| "  ".repeat(depth);
+        match self {
+            Value::Array(items) if !items.is_empty() => {
+                text.push_str("[\n");
+                for (i, item) in items.iter().enumerate() {
+                    text.push_str(&indent(depth + 1));
+                    item.write_pretty(text, depth + 1);
+                    text.push_str(if i + 1 < items.len() { ",\n" } else { "\n" });
+                }
+                text.push_str(&indent(depth));
+                text.push(']');
+            }
+            Value::Object(fields) if !fields.is_empty() => {
+                text.push_str("{\n");
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    text.push_str(&format!("{}{}: ", indent(depth + 1), quote(key)));
+                    value.write_pretty(text, depth + 1);
+                    text.push_str(if i + 1 < fields.len() { ",\n" } else { "\n" });
+                }
+                text.push_str(&indent(depth));
+                text.push('}');
+            }
+            other => text.push_str(&other.to_string()),
+        }
+    }
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        return match self {
+            Value::Null => write!(f, "null"),
+            Value::Bool(b) => write!(f, "{}", b),
+            // json has no NaN or infinity
+            Value::Number(n) if !n.is_finite() => write!(f, "null"),
+            // whole numbers like frame counts read better without ".0"
+            Value::Number(n) if n.fract() == 0.0 && n.abs() < 1e15 => write!(f, "{}", *n as i64),
+            Value::Number(n) => write!(f, "{}", n),
+            Value::Str(s) => write!(f, "{}", quote(s)),
+            Value::Array(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    match i {
+                        0 => write!(f, "{}", item)?,
+                        _ => write!(f, ",{}", item)?,
+                    }
+                }
+                write!(f, "]")
+            }
+            Value::Object(fields) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    match i {
+                        0 => write!(f, "{}:{}", quote(key), value)?,
+                        _ => write!(f, ",{}:{}", quote(key), value)?,
+                    }
+                }
+                write!(f, "}}")
+            }
+        };
+    }
+}
+
+pub fn quote(s: &str) -> String {
+    let mut quoted = "\"".to_string();
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    return quoted;
+}
+
+// --- PARSING --- //
+
+// deeper than anything vic reads, and shallow enough for any thread's stack
+const MAX_DEPTH: usize = 100;
+
+pub fn parse(text: &str) -> Result<Value, String> {
+    let mut parser = Parser {
+        chars: text.chars().collect(),
+        pos: 0,
+        depth: 0,
+    };
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    return match parser.pos == parser.chars.len() {
+        true => Ok(value),
+        false => Err(parser.error("unexpected text after the value")),
+    };
+}
+
+// a plain recursive descent parser, one char at a time
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    depth: usize, // of arrays and objects around the current value
+}
+
+impl Parser {
+    fn error(&self, msg: &str) -> String {
+        return format!("{} at character {}", msg, self.pos + 1);
+    }
+
+    fn peek(&self) -> Option<char> {
+        return self.chars.get(self.pos).copied();
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t' | '\n' | '\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, word: &str) -> Result<(), String> {
+        for c in word.chars() {
+            match self.peek() == Some(c) {
+                true => self.pos += 1,
+                false => return Err(self.error(&format!("expected {}", word))),
+            }
+        }
+        return Ok(());
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        self.skip_whitespace();
+        return match self.peek() {
+            None => Err(self.error("expected a value")),
+            Some('n') => self.expect("null").map(|_| Value::Null),
+            Some('t') => self.expect("true").map(|_| Value::Bool(true)),
+            Some('f') => self.expect("false").map(|_| Value::Bool(false)),
+            Some('"') => self.string().map(Value::Str),
+            Some('[') => self.nested(Parser::array),
+            Some('{') => self.nested(Parser::object),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.number(),
+            Some(c) => Err(self.error(&format!("unexpected {:?}", c))),
+        };
+    }
+
+    fn nested(&mut self, parse: fn(&mut Parser) -> Result<Value, String>) -> Result<Value, String> {
+        // arrays and objects recurse, so a line like [[[[... from an --ipc client
+        // could otherwise overflow the stack
+        if self.depth == MAX_DEPTH {
+            return Err(self.error("nested too deeply"));
+        }
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        return value;
+    }
+
+    fn number(&mut self) -> Result<Value, String> {
+        let start = self.pos;
+        while matches!(self.peek(), Some('0'..='9' | '-' | '+' | '.' | 'e' | 'E')) {
+            self.pos += 1;
+        }
+        let text = self.chars[start..self.pos].iter().collect::<String>();
+        return text
+            .parse::<f64>()
+            .map(Value::Number)
+            .map_err(|_| format!("bad number {} at character {}", text, start + 1));
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect("\"")?;
+        let mut s = String::new();
+        loop {
+            let c = self.peek().ok_or(self.error("unfinished string"))?;
+            self.pos += 1;
+            match c {
+                '"' => return Ok(s),
+                '\\' => {
+                    let escaped = self.peek().ok_or(self.error("unfinished string"))?;
+                    self.pos += 1;
+                    match escaped {
+                        '"' | '\\' | '/' => s.push(escaped),
+                        'n' => s.push('\n'),
+                        'r' => s.push('\r'),
+                        't' => s.push('\t'),
+                        'b' => s.push('\u{8}'),
+                        'f' => s.push('\u{c}'),
+                        'u' => s.push(self.unicode_escape()?),
+                        _ => return Err(self.error("bad escape")),
+                    }
+                }
+                c => s.push(c),
+            }
+        }
+    }
+
+    fn unicode_escape(&mut self) -> Result<char, String> {
+        // the 4 hex digits after \u, or two of them for a surrogate pair
+        let first = self.hex4()?;
+        let code = match first {
+            0xd800..=0xdbff => {
+                self.expect("\\u")?;
+                let second = self.hex4()?;
+                match second {
+                    0xdc00..=0xdfff => 0x10000 + ((first - 0xd800) << 10) + (second - 0xdc00),
+                    _ => return Err(self.error("bad surrogate pair")),
+                }
+            }
+            _ => first,
+        };
+        return char::from_u32(code).ok_or(self.error("bad \\u escape"));
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let digits = self
+            .chars
+            .get(self.pos..self.pos + 4)
+            .ok_or(self.error("bad \\u escape"))?
+            .iter()
+            .collect::<String>();
+        let code = u32::from_str_radix(&digits, 16).map_err(|_| self.error("bad \\u escape"))?;
+        self.pos += 4;
+        return Ok(code);
+    }
+
+    fn array(&mut self) -> Result<Value, String> {
+        self.expect("[")?;
+        let mut items = Vec::<Value>::new();
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.pos += 1;
+            return Ok(Value::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some(']') => {
+                    self.pos += 1;
+                    return Ok(Value::Array(items));
+                }
+                _ => return Err(self.error("expected , or ]")),
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<Value, String> {
+        self.expect("{")?;
+        let mut fields = Vec::<(String, Value)>::new();
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.pos += 1;
+            return Ok(Value::Object(fields));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.skip_whitespace();
+            self.expect(":")?;
+            fields.push((key, self.value()?));
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some('}') => {
+                    self.pos += 1;
+                    return Ok(Value::Object(fields));
+                }
+                _ => return Err(self.error("expected , or }")),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_json_parses_back_what_it_prints() {
+    let value = parse(
+        r#" {"command": ["seek", 12.5, -3e2], "id": 1, "ok": true,
+        "x": null, "s": "tab\t \"q\" é 🎬", "empty": [], "o": {}} "#,
+    )
+    .unwrap();
+    assert_eq!(value.get("command").unwrap().as_array().unwrap().len(), 3);
+    assert_eq!(value.get("id").unwrap().as_f64(), Some(1.0));
+    assert_eq!(value.get("s").unwrap().as_str(), Some("tab\t \"q\" é 🎬"));
+    assert_eq!(
+        value.to_string(),
+        r#"{"command":["seek",12.5,-300],"id":1,"ok":true,"x":null,"s":"tab\t \"q\" é 🎬","empty":[],"o":{}}"#
+    );
+    assert_eq!(parse(&value.to_string()).unwrap(), value);
+    assert_eq!(parse(&value.pretty()).unwrap(), value);
+    assert_eq!(
+        Value::object(vec![("a", Value::Array(vec![Value::Number(1.0)]))]).pretty(),
+        "{\n  \"a\": [\n    1\n  ]\n}"
+    );
+
+    assert!(parse("[1, 2").is_err());
+    assert!(parse("{\"a\" 1}").is_err());
+    assert!(parse("1 2").is_err());
+
+    let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+    assert!(parse(&nested(MAX_DEPTH)).is_ok());
+    assert_eq!(
+        parse(&nested(MAX_DEPTH + 1)),
+        Err(format!("nested too deeply at character {}", MAX_DEPTH + 1))
+    );
+    assert!(parse(&"[".repeat(100_000)).is_err());
+}
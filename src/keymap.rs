@@ -0,0 +1,638 @@
+// keybindings: which keys trigger which actions
+//
+// a keymap starts from a preset (default, vim, or mpv), then the [keys] section
+// of the config file rebinds any actions by name:
+//
+//   [keys]
+//   preset = "vim"
+//   make-marker = "m"
+//   play-pause = ["space", "p"]
+//   redo = "ctrl+r"
+//   save-frame = []   # unbound
+//
+// a key can only do one thing, so binding it to one action unbinds it from any other.
+// ctrl+c always quits, no matter the keymap, so there's always a way out
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::config::ConfigFile;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    Quit,
+    TogglePause,
+    ToggleHelp,
+    ToggleInfo,
+    SeekBack5s,
+    SeekForward5s,
+    SeekBack15s,
+    SeekForward15s,
+    SeekToPercent(u32), // 0, 10, ... 90
+    AdvanceFrame,
+    PrevMarker,
+    NextMarker,
+    MakeMarker,
+    RemoveMarker,
+    NudgeBackFrame,
+    NudgeForwardFrame,
+    NudgeBackSec,
+    NudgeForwardSec,
+    ToggleKept,
+    LabelSegment,
+    SetInPoint,
+    SetOutPoint,
+    DeleteRange,
+    SaveFrame,
+    Undo,
+    Redo,
+}
+
+const ALL_ACTIONS: [Action; 35] = [
+    Action::Quit,
+    Action::TogglePause,
+    Action::ToggleHelp,
+    Action::ToggleInfo,
+    Action::SeekBack5s,
+    Action::SeekForward5s,
+    Action::SeekBack15s,
+    Action::SeekForward15s,
+    Action::SeekToPercent(0),
+    Action::SeekToPercent(10),
+    Action::SeekToPercent(20),
+    Action::SeekToPercent(30),
+    Action::SeekToPercent(40),
+    Action::SeekToPercent(50),
+    Action::SeekToPercent(60),
+    Action::SeekToPercent(70),
+    Action::SeekToPercent(80),
+    Action::SeekToPercent(90),
+    Action::AdvanceFrame,
+    Action::PrevMarker,
+    Action::NextMarker,
+    Action::MakeMarker,
+    Action::RemoveMarker,
+    Action::NudgeBackFrame,
+    Action::NudgeForwardFrame,
+    Action::NudgeBackSec,
+    Action::NudgeForwardSec,
+    Action::ToggleKept,
+    Action::LabelSegment,
+    Action::SetInPoint,
+    Action::SetOutPoint,
+    Action::DeleteRange,
+    Action::SaveFrame,
+    Action::Undo,
+    Action::Redo,
+];
+
+pub const PRESETS: [&str; 3] = ["default", "vim", "mpv"];
+
+impl Action {
+    pub fn name(&self) -> String {
+        return match self {
+            Action::Quit => "quit",
+            Action::TogglePause => "play-pause",
+            Action::ToggleHelp => "help",
+            Action::ToggleInfo => "info",
+            Action::SeekBack5s => "seek-back-5s",
+            Action::SeekForward5s => "seek-forward-5s",
+            Action::SeekBack15s => "seek-back-15s",
+            Action::SeekForward15s => "seek-forward-15s",
+            Action::SeekToPercent(percent) => return format!("seek-to-{}", percent),
+            Action::AdvanceFrame => "advance-frame",
+            Action::PrevMarker => "prev-marker",
+            Action::NextMarker => "next-marker",
+            Action::MakeMarker => "make-marker",
+            Action::RemoveMarker => "remove-marker",
+            Action::NudgeBackFrame => "nudge-back-frame",
+            Action::NudgeForwardFrame => "nudge-forward-frame",
+            Action::NudgeBackSec => "nudge-back-sec",
+            Action::NudgeForwardSec => "nudge-forward-sec",
+            Action::ToggleKept => "keep-discard",
+            Action::LabelSegment => "label-segment",
+            Action::SetInPoint => "set-in-point",
+            Action::SetOutPoint => "set-out-point",
+            Action::DeleteRange => "delete-range",
+            Action::SaveFrame => "save-frame",
+            Action::Undo => "undo",
+            Action::Redo => "redo",
+        }
+        .to_string();
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        return ALL_ACTIONS
+            .iter()
+            .find(|action| action.name() == name)
+            .copied();
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Key {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl Key {
+    pub fn from_event(keyevent: &KeyEvent) -> Self {
+        // shift is already part of a character, like J, and terminals disagree on reporting it
+        let modifiers = match keyevent.code {
+            KeyCode::Char(_) => keyevent.modifiers - KeyModifiers::SHIFT,
+            _ => keyevent.modifiers,
+        };
+        return Key {
+            code: keyevent.code,
+            modifiers: modifiers,
+        };
+    }
+
+    pub fn to_event(&self) -> KeyEvent {
+        return KeyEvent::new(self.code, self.modifiers);
+    }
+
+    pub fn label(&self, is_short: bool) -> String {
+        // short labels fit the on-screen controls, like ^r, and long labels are for --help, like ctrl+r
+        let mut label = String::new();
+        for (modifier, short, long) in [
+            (KeyModifiers::CONTROL, "^", "ctrl+"),
+            (KeyModifiers::ALT, "M-", "alt+"),
+            (KeyModifiers::SHIFT, "S-", "shift+"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                label.push_str(if is_short { short } else { long });
+            }
+        }
+        let key_name = match self.code {
+            KeyCode::Left => "←".to_string(),
+            KeyCode::Right => "→".to_string(),
+            KeyCode::Up => "↑".to_string(),
+            KeyCode::Down => "↓".to_string(),
+            code => code_name(code),
+        };
+        label.push_str(&key_name);
+        return label;
+    }
+}
+
+impl std::fmt::Display for Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        // the same format that parses, like ctrl+r or left
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "ctrl+"),
+            (KeyModifiers::ALT, "alt+"),
+            (KeyModifiers::SHIFT, "shift+"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{}", name)?;
+            }
+        }
+        return write!(f, "{}", code_name(self.code));
+    }
+}
+
+fn code_name(code: KeyCode) -> String {
+    return match code {
+        KeyCode::Char(' ') => "space".to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::Left => "left".to_string(),
+        KeyCode::Right => "right".to_string(),
+        KeyCode::Up => "up".to_string(),
+        KeyCode::Down => "down".to_string(),
+        KeyCode::PageUp => "pgup".to_string(),
+        KeyCode::PageDown => "pgdn".to_string(),
+        KeyCode::Home => "home".to_string(),
+        KeyCode::End => "end".to_string(),
+        KeyCode::Enter => "enter".to_string(),
+        KeyCode::Tab => "tab".to_string(),
+        KeyCode::Backspace => "backspace".to_string(),
+        KeyCode::Delete => "delete".to_string(),
+        KeyCode::Insert => "insert".to_string(),
+        KeyCode::Esc => "esc".to_string(),
+        KeyCode::F(n) => format!("f{}", n),
+        other => format!("{:?}", other).to_lowercase(),
+    };
+}
+
+impl std::str::FromStr for Key {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // like m, M, space, left, pgup, f5, ctrl+r, alt+m, or ctrl++
+        let (modifier_names, key_name) = match s.strip_suffix("++") {
+            Some(modifier_names) => (modifier_names, "+"),
+            None => match s.rsplit_once('+') {
+                Some((modifier_names, key_name)) if !key_name.is_empty() => {
+                    (modifier_names, key_name)
+                }
+                _ => ("", s),
+            },
+        };
+        let mut modifiers = KeyModifiers::NONE;
+        for modifier_name in modifier_names.split('+').filter(|name| !name.is_empty()) {
+            modifiers |= match modifier_name.to_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" | "meta" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(format!("unknown modifier {} in key {}", modifier_name, s)),
+            };
+        }
+        let mut chars = key_name.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) if !"←→↑↓".contains(c) => KeyCode::Char(c),
+            _ => match key_name.to_lowercase().as_str() {
+                "space" => KeyCode::Char(' '),
+                "left" | "←" => KeyCode::Left,
+                "right" | "→" => KeyCode::Right,
+                "up" | "↑" => KeyCode::Up,
+                "down" | "↓" => KeyCode::Down,
+                "pgup" | "pageup" => KeyCode::PageUp,
+                "pgdn" | "pagedown" => KeyCode::PageDown,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "enter" | "return" => KeyCode::Enter,
+                "tab" => KeyCode::Tab,
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" | "ins" => KeyCode::Insert,
+                "esc" | "escape" => KeyCode::Esc,
+                name => match name.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                    Some(n) if (1..=12).contains(&n) => KeyCode::F(n),
+                    _ => return Err(format!("unknown key {}", s)),
+                },
+            },
+        };
+        return Ok(Key::from_event(&KeyEvent::new(code, modifiers)));
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    preset: String,
+    bindings: Vec<(Key, Action)>, // in order of preference, so the first key of an action is shown in help
+}
+
+impl Keymap {
+    pub fn preset(name: &str) -> Result<Self, String> {
+        let mut keymap = Keymap {
+            preset: name.to_string(),
+            bindings: vec![],
+        };
+        let default_bindings: [(Action, &[&str]); 26] = [
+            (Action::Quit, &["q"]),
+            (Action::TogglePause, &["space"]),
+            (Action::ToggleHelp, &["?", "h"]), // h is the older key, kept for muscle memory
+            (Action::ToggleInfo, &["I"]),      // i is already the in point
+            (Action::SeekBack5s, &["left"]),
+            (Action::SeekForward5s, &["right"]),
+            (Action::SeekBack15s, &["j"]),
+            (Action::SeekForward15s, &["l"]),
+            (Action::SeekToPercent(0), &["0"]),
+            (Action::AdvanceFrame, &["."]),
+            (Action::PrevMarker, &["J"]),
+            (Action::NextMarker, &["L"]),
+            (Action::MakeMarker, &["m"]),
+            (Action::RemoveMarker, &["M"]),
+            (Action::NudgeBackFrame, &["["]),
+            (Action::NudgeForwardFrame, &["]"]),
+            (Action::NudgeBackSec, &["{"]),
+            (Action::NudgeForwardSec, &["}"]),
+            (Action::ToggleKept, &["s"]),
+            (Action::LabelSegment, &["r"]),
+            (Action::SetInPoint, &["i"]),
+            (Action::SetOutPoint, &["o"]),
+            (Action::DeleteRange, &["x"]),
+            (Action::SaveFrame, &["p"]),
+            (Action::Undo, &["u"]),
+            (Action::Redo, &["ctrl+r"]),
+        ];
+        // only differences from the default
+        let preset_bindings: &[(Action, &[&str])] = match name {
+            "default" => &[],
+            "vim" => &[
+                (Action::ToggleHelp, &["?"]),
+                (Action::SeekBack5s, &["h", "left"]),
+                (Action::SeekForward5s, &["l", "right"]),
+                (Action::SeekBack15s, &["b"]),
+                (Action::SeekForward15s, &["w"]),
+                (Action::PrevMarker, &["B"]),
+                (Action::NextMarker, &["W"]),
+            ],
+            "mpv" => &[
+                (Action::TogglePause, &["space", "p"]),
+                (Action::SeekBack15s, &["down"]),
+                (Action::SeekForward15s, &["up"]),
+                (Action::PrevMarker, &["pgup"]),
+                (Action::NextMarker, &["pgdn"]),
+                (Action::ToggleKept, &["k"]),
+                (Action::SaveFrame, &["s"]),
+            ],
+            _ => {
+                return Err(format!(
+                    "unknown keymap preset {}\nexpected one of {}",
+                    name,
+                    PRESETS.join(", ")
+                ))
+            }
+        };
+        for (action, key_names) in default_bindings.iter().chain(preset_bindings) {
+            // presets only use valid keys, so unwrapping is safe
+            let keys = key_names.iter().map(|key_name| key_name.parse().unwrap());
+            keymap.bind(*action, keys.collect());
+        }
+        for percent in (10..=90).step_by(10) {
+            let key = Key::from_event(&KeyEvent::from(KeyCode::Char(
+                char::from_digit(percent / 10, 10).unwrap(),
+            )));
+            keymap.bind(Action::SeekToPercent(percent), vec![key]);
+        }
+        return Ok(keymap);
+    }
+
+    pub fn bind(&mut self, action: Action, keys: Vec<Key>) {
+        // replaces the action's keys, and steals them from any other actions
+        self.bindings
+            .retain(|(key, other_action)| *other_action != action && !keys.contains(key));
+        self.bindings
+            .extend(keys.into_iter().map(|key| (key, action)));
+    }
+
+    pub fn action_for(&self, keyevent: &KeyEvent) -> Option<Action> {
+        let key = Key::from_event(keyevent);
+        return self
+            .bindings
+            .iter()
+            .find(|(other_key, _)| *other_key == key)
+            .map(|(_, action)| *action);
+    }
+
+    pub fn keys_for(&self, action: Action) -> Vec<Key> {
+        return self
+            .bindings
+            .iter()
+            .filter(|(_, other_action)| *other_action == action)
+            .map(|(key, _)| *key)
+            .collect();
+    }
+
+    pub fn config_text(&self) -> String {
+        // every binding, as a [keys] section that loads back into this same keymap
+        let mut text = format!("[keys]\npreset = {}\n", crate::json::quote(&self.preset));
+        for action in ALL_ACTIONS {
+            let keys = self
+                .keys_for(action)
+                .iter()
+                .map(|key| crate::json::quote(&key.to_string()))
+                .collect::<Vec<String>>();
+            let value = match keys.len() {
+                1 => keys[0].clone(),
+                _ => format!("[{}]", keys.join(", ")),
+            };
+            text.push_str(&format!("{} = {}\n", action.name(), value));
+        }
+        return text;
+    }
+
+    pub fn label(&self, actions: &[Action], is_short: bool) -> Option<String> {
+        // the first key of each action, like j/l for back/forwards,
+        // or None if any is unbound, since half a pair would be confusing.
+        // percent seeks are a span instead, like 0-9
+        let first_keys = actions
+            .iter()
+            .map(|action| {
+                self.keys_for(*action)
+                    .first()
+                    .map(|key| key.label(is_short))
+            })
+            .collect::<Option<Vec<String>>>()?;
+        let separator = match actions
+            .iter()
+            .all(|action| matches!(action, Action::SeekToPercent(_)))
+        {
+            true => "-",
+            false => "/",
+        };
+        return Some(first_keys.join(separator));
+    }
+}
+
+pub fn load(config: &ConfigFile, preset_override: Option<&str>) -> Result<Keymap, String> {
+    // preset from --keymap, else from the config file, else default; then any overrides
+    let in_file = config.in_file();
+    let config_preset = match config.get("keys.preset") {
+        Some(value) => Some(
+            value
+                .as_strings()
+                .filter(|names| names.len() == 1)
+                .ok_or(format!(
+                    "keys.preset should be a string, like \"vim\"{}",
+                    in_file
+                ))?
+                .remove(0),
+        ),
+        None => None,
+    };
+    let preset = preset_override
+        .map(str::to_string)
+        .or(config_preset)
+        .unwrap_or("default".to_string());
+    let mut keymap = Keymap::preset(&preset)?;
+    for (action_name, value) in config.section("keys") {
+        if action_name == "preset" {
+            continue;
+        }
+        let action = Action::from_name(action_name).ok_or(format!(
+            "unknown action {} in [keys]{}",
+            action_name, in_file
+        ))?;
+        let keys = value
+            .as_strings()
+            .ok_or(format!(
+                "keys.{} should be a key or list of keys, like \"m\" or [\"m\", \"enter\"]{}",
+                action_name, in_file
+            ))?
+            .iter()
+            .map(|key_name| key_name.parse::<Key>())
+            .collect::<Result<Vec<Key>, String>>()
+            .map_err(|e| format!("{} for keys.{}{}", e, action_name, in_file))?;
+        keymap.bind(action, keys);
+    }
+    return Ok(keymap);
+}
+
+pub fn action_names_text() -> String {
+    // every action name for --help, wrapped, with the percent seeks summarized
+    let mut names = ALL_ACTIONS
+        .iter()
+        .filter(|action| !matches!(action, Action::SeekToPercent(_)))
+        .map(|action| action.name())
+        .collect::<Vec<String>>();
+    names.push("seek-to-0 through seek-to-90".to_string());
+    let mut text = String::new();
+    let mut line = String::new();
+    for name in names {
+        if !line.is_empty() && line.len() + name.len() > 50 {
+            text.push_str(&format!("     {},\n", line));
+            line.clear();
+        }
+        if !line.is_empty() {
+            line.push_str(", ");
+        }
+        line.push_str(&name);
+    }
+    text.push_str(&format!("     {}\n", line));
+    return text;
+}
+
+pub fn help_text(keymap: &Keymap) -> String {
+    // like:
+    //
+    //   [ segment mode ]
+    //
+    //     m ....... make marker
+    //     space ... play/pause
+    //
+    let sections: [(&str, &[(&[Action], &str)]); 3] = [
+        (
+            "segment mode",
+            &[
+                (&[Action::MakeMarker], "make marker"),
+                (&[Action::ToggleKept], "keep/discard segment"),
+                (&[Action::LabelSegment], "label segment"),
+                (&[Action::TogglePause], "play/pause"),
+                (
+                    &[Action::SeekBack15s, Action::SeekForward15s],
+                    "back/forwards 15 secs",
+                ),
+                (
+                    &[Action::SeekBack5s, Action::SeekForward5s],
+                    "back/forwards 5 secs",
+                ),
+                (
+                    &[Action::SeekToPercent(0), Action::SeekToPercent(90)],
+                    "seek to 0%, 10%, etc",
+                ),
+                (&[Action::AdvanceFrame], "advance one frame"),
+                (&[Action::SaveFrame], "save frame as png"),
+                (&[Action::Undo], "undo"),
+                (&[Action::Redo], "redo"),
+                (&[Action::ToggleHelp], "show/hide controls"),
+                (&[Action::ToggleInfo], "show/hide video info"),
+                (&[Action::Quit], "finish"),
+            ],
+        ),
+        (
+            "marker mode",
+            &[
+                (
+                    &[Action::PrevMarker, Action::NextMarker],
+                    "goto prev/next marker",
+                ),
+                (&[Action::RemoveMarker], "delete marker"),
+                (
+                    &[Action::NudgeBackFrame, Action::NudgeForwardFrame],
+                    "nudge marker back/forwards 1 frame",
+                ),
+                (
+                    &[Action::NudgeBackSec, Action::NudgeForwardSec],
+                    "nudge marker back/forwards 1 sec",
+                ),
+            ],
+        ),
+        (
+            "ranges",
+            &[
+                (&[Action::SetInPoint], "set in point"),
+                (&[Action::SetOutPoint], "set out point, making a range"),
+                (&[Action::DeleteRange], "delete range under playhead"),
+            ],
+        ),
+    ];
+    let mut text = String::new();
+    for (i, (section_name, lines)) in sections.iter().enumerate() {
+        if i > 0 {
+            text.push('\n');
+        }
+        text.push_str(&format!("   [ {} ]\n\n", section_name));
+        for (actions, description) in lines.iter() {
+            if let Some(label) = keymap.label(actions, false) {
+                let num_dots = 8usize.saturating_sub(label.chars().count()).max(2);
+                text.push_str(&format!(
+                    "     {} {} {}\n",
+                    label,
+                    ".".repeat(num_dots),
+                    description
+                ));
+            }
+        }
+    }
+    return text;
+}
+
+#[cfg(test)]
+#[test]
+fn test_config_overrides_preset_keys() {
+    let config = ConfigFile {
+        path: None,
+        entries: crate::config::parse(
+            "[keys]\npreset = 'vim'\nmake-marker = ['enter', 'ctrl+m']\nundo = []",
+        )
+        .unwrap(),
+    };
+    let keymap = load(&config, None).unwrap();
+    let press =
+        |code: KeyCode, modifiers: KeyModifiers| keymap.action_for(&KeyEvent::new(code, modifiers));
+
+    // from the vim preset
+    assert_eq!(
+        press(KeyCode::Char('h'), KeyModifiers::NONE),
+        Some(Action::SeekBack5s)
+    );
+    assert_eq!(
+        press(KeyCode::Char('W'), KeyModifiers::SHIFT),
+        Some(Action::NextMarker)
+    );
+    // from the config, which also unbinds m
+    assert_eq!(
+        press(KeyCode::Enter, KeyModifiers::NONE),
+        Some(Action::MakeMarker)
+    );
+    assert_eq!(
+        press(KeyCode::Char('m'), KeyModifiers::CONTROL),
+        Some(Action::MakeMarker)
+    );
+    assert_eq!(press(KeyCode::Char('m'), KeyModifiers::NONE), None);
+    assert_eq!(press(KeyCode::Char('u'), KeyModifiers::NONE), None);
+    // untouched defaults
+    assert_eq!(
+        press(KeyCode::Char('r'), KeyModifiers::CONTROL),
+        Some(Action::Redo)
+    );
+    assert_eq!(
+        press(KeyCode::Char('7'), KeyModifiers::NONE),
+        Some(Action::SeekToPercent(70))
+    );
+
+    assert_eq!(
+        keymap.label(&[Action::SeekBack15s, Action::SeekForward15s], true),
+        Some("b/w".to_string())
+    );
+    assert_eq!(keymap.label(&[Action::Undo], true), None);
+    assert!(help_text(&keymap).contains("     enter ... make marker\n"));
+    assert!(load(&config, Some("emacs")).is_err());
+}
+
+#[cfg(test)]
+#[test]
+fn test_printed_keymap_loads_back_the_same() {
+    for preset in PRESETS {
+        let keymap = Keymap::preset(preset).unwrap();
+        let config = ConfigFile {
+            path: None,
+            entries: crate::config::parse(&keymap.config_text()).unwrap(),
+        };
+        let reloaded = load(&config, None).unwrap();
+        for action in ALL_ACTIONS {
+            assert_eq!(keymap.keys_for(action), reloaded.keys_for(action));
+        }
+    }
+}
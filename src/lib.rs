@@ -0,0 +1,109 @@
+// vic
+//
+// a terminal video cutter, as a library.
+// the vic binary is the TUI in app.rs, built on these pieces,
+// which other rust tools can also use without shelling out to vic:
+//
+//   probe ..... VideoMetadata and ExtendedMetadata, from ffprobe
+//   frames .... frame sources (ffmpeg, test patterns, images), drawn as terminal graphics
+//   timeline .. markers, the segments between them, and in/out ranges
+//   export .... cut recipes: the ffmpeg commands for each kept segment and range
+//   recipe .... recipes written out as shell scripts, json, etc, for --dry-run
+//   jobs ...... running recipes, with progress bars
+//
+// the app's own modules stay private: app, tui, keymap, config, recording, ipc,
+// report (for --print), json (a small reader and writer, no serde), and error,
+// though VicError is re-exported below
+//
+// requires:
+// - libchafa, dynamically linked (maybe static build eventually)
+// - ffmpeg on $PATH, tested with version 3.4.8
+// - one direct rust dependency (crossterm)
+
+#![allow(unused)]
+#![allow(non_snake_case)]
+
+use std::error::Error;
+use std::io::Write;
+
+mod app;
+mod config;
+mod error;
+pub mod export;
+pub mod frames;
+mod ipc;
+pub mod jobs;
+mod json;
+mod keymap;
+pub mod probe;
+pub mod recipe;
+mod recording;
+mod report;
+pub mod timeline;
+mod tui;
+pub use crate::error::VicError;
+pub use crate::probe::{
+    get_ffprobe_extended_metadata, get_ffprobe_video_metadata, ExtendedMetadata, VideoMetadata,
+};
+
+// the vic binary, see main.rs. not part of the library
+#[doc(hidden)]
+pub fn run_app() {
+    app::main();
+}
+
+// --- LOGS --- //
+
+// grouping some static functions
+// they access the init-once global constant LOGFILE
+pub struct Logs {}
+impl Logs {
+    pub fn init(path: Option<std::path::PathBuf>) -> Result<(), Box<dyn Error>> {
+        match path {
+            None => LOGFILE.set(None),
+            Some(_path) => {
+                let file = std::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(_path)?;
+                LOGFILE.set(Some(std::sync::Mutex::new(file)));
+                Ok(())
+            }
+        };
+        Ok(())
+    }
+
+    pub fn append(msg: String) {
+        if let Some(Some(file)) = LOGFILE.get() {
+            if let Ok(mut file) = file.lock() {
+                writeln!(file, "{}", msg).ok();
+            }
+        }
+    }
+}
+
+#[macro_export]
+macro_rules! log {
+    // usage:
+    // log!("{}", some_var)
+    //
+    // more convenient than:
+    // Logs::append(format!("{}", some_var))
+    ($($arg:tt)*) => {
+        $crate::Logs::append(format!($($arg)*))
+    };
+}
+
+// LOGFILE is None if user doesn't provide log filepath, else Some(Mutex(File))
+// mutex file is safe for writing from multiple threads or vic processes
+static LOGFILE: std::sync::OnceLock<Option<std::sync::Mutex<std::fs::File>>> =
+    std::sync::OnceLock::new();
+
+pub type Seconds = f64; // to indicate when we're using units of time
+
+// note that crossterm uses u16, like in terminal::size() or MoveToColumn()
+// and that chafa uses i32, like in canvas.set_geometry()
+//
+// assuming terminal size is under u16::MAX = 65_535 cols/lines
+pub type Columns = u16;
+pub type Rows = u16;
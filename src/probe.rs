@@ -0,0 +1,670 @@
+// probing a video with ffprobe, before decoding or cutting it,
+// and the longer list of details for `vic probe` and the info panel
+
+use std::io::Read;
+
+use crate::error::VicError;
+use crate::json::{self, Value};
+use crate::{log, Logs, Seconds};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VideoMetadata {
+    pub width_px: i32,  // pixels
+    pub height_px: i32, // pixels
+    pub fps: f64,
+    pub duration_secs: Seconds,
+    // maybe max_frame_number: u32, // derived from duration and fps, for convenience
+    pub seconds_per_frame: Seconds, // derived from fps, for convenience
+}
+
+pub fn get_ffprobe_video_metadata(video_filepath: &str) -> Result<VideoMetadata, VicError> {
+    // run ffprobe, a sibling tool of ffmpeg, then parse output into VideoMetadata
+    //
+    // note/TODO: if metadata is corrupted, ffprobe will report wrong data,
+    // but ffmpeg will have correct data
+    // so maybe dont use ffprobe
+    // https://stackoverflow.com/q/6239350#comment92617706_6239379
+    //
+    // to learn more:
+    // https://ffmpeg.org//ffprobe.html#Main-options
+    // https://stackoverflow.com/a/24488789
+    let plaintext_metadata = _run_ffprobe_command(video_filepath)?;
+    if plaintext_metadata.trim().is_empty() {
+        return Err(VicError::UnsupportedStream(format!(
+            "no video stream found in {}",
+            video_filepath
+        )));
+    }
+    return _parse_ffprobe_output(plaintext_metadata).map_err(VicError::UnsupportedStream);
+}
+
+fn _run_ffprobe_command(video_filepath: &str) -> Result<String, VicError> {
+    // `ffprobe -show_entries stream:format` default output formatting is:
+    // ```
+    // [FORMAT]
+    // filename=abc
+    // nb_streams=1
+    // duration=...
+    // size=...
+    // [/FORMAT]
+    // [STREAM]
+    // duration=...
+    // avg_frame_rate=...
+    // nb_frames=...
+    // [/STREAM]
+    // [STREAM]
+    // ...
+    // [/STREAM]
+    // ```
+    //
+    // but after more specific -show_entries -select_stream -print_format, output becomes:
+    // ```
+    // width=643,height=528,r_frame_rate=30/1
+    // duration=ss.microseconds
+    // ```
+    // (note the newline)
+    let mut probe_process = std::process::Command::new("ffprobe");
+    probe_process
+        // verbosity. i dont think this matters here
+        // .args(["-v", "error"])
+        //
+        // only need the first [STREAM] (ie. don't need secondary streams like audio)
+        .args(["-select_streams", "v:0"])
+        .args([
+            "-show_entries",
+            //
+            // get the width, height, and r_frame_rate properties from the [STREAM] section
+            // and get the duration property from the [FORMAT] section
+            //
+            // note that [FORMAT]::duration seems reliable,
+            // while [STREAM]::duration could be missing
+            //
+            // also note: the output could be in any order
+            // https://ffmpeg.org//ffprobe.html#:~:text=the%20order%20of%20specification%20of%20the%20local%20section%20entries%20is%20not%20honored%20in%20the%20output%2C%20and%20the%20usual%20display%20order%20will%20be%20retained.
+            "stream=width,height,r_frame_rate:
+                format=duration",
+        ])
+        //
+        .args([
+            "-print_format",
+            // some -print_formats include default, json, and csv/compact
+            // we use compact; ignore the [STREAM/FORMAT] section labels; and separate with commas
+            //
+            // https://ffmpeg.org//ffprobe.html#default
+            "compact=
+                print_section=0:item_sep=,",
+        ])
+        .arg(&video_filepath);
+    return _ffprobe_output(&mut probe_process);
+}
+
+fn _ffprobe_output(cmd: &mut std::process::Command) -> Result<String, VicError> {
+    let probe_process = cmd
+        .output()
+        .map_err(|e| VicError::from_spawn_error("ffprobe", e))?;
+
+    let plain_output = String::from_utf8_lossy(&probe_process.stdout).to_string();
+    let err = String::from_utf8_lossy(&probe_process.stderr).to_string();
+    log!("{}\n{}", plain_output, err);
+
+    // like a missing file or unknown format. the last line of stderr usually says why
+    if !probe_process.status.success() {
+        let reason = err
+            .lines()
+            .rev()
+            .find(|line| !line.trim().is_empty())
+            .unwrap_or("ffprobe failed");
+        return Err(VicError::DecodeFailed(reason.to_string()));
+    }
+    return Ok(plain_output);
+}
+
+fn _parse_ffprobe_output(plaintext_metadata: String) -> Result<VideoMetadata, String> {
+    // given:
+    // ```
+    // width=643,height=528,r_frame_rate=30/1
+    // duration=ss.microseconds
+    // ```
+    //
+    // replace newlines with comma, then split on commas to get:
+    // ```
+    // key=val,key=val,key=val
+    // ```
+    //
+    // and parse into VideoMetadata struct
+
+    // note that ffprobe could return windows newlines
+    let single_line_output = plaintext_metadata
+        .trim()
+        .replace("\r\n", ",")
+        .replace('\n', ",");
+
+    let mut ffprobe_properties = std::collections::HashMap::<&str, &str>::new();
+
+    for line in single_line_output.split(',') {
+        let (key, val) = line
+            .split_once("=")
+            .ok_or(format!("failed to split part of ffprobe output {} -- expected key=val. ffprobe command probably failed", line))
+            .map_err(|e| format!("failed to parse part of ffprobe output {}", line))?;
+
+        ffprobe_properties.insert(key, val);
+    }
+
+    // parse keyval map of strings into typed struct
+
+    let width = ffprobe_properties
+        .get("width")
+        .ok_or(format!("failed to get width {}", plaintext_metadata))?
+        .parse::<i32>()
+        .map_err(|e| format!("failed to parse {} {}", e, plaintext_metadata))?;
+    let height = ffprobe_properties
+        .get("height")
+        .ok_or(format!("failed to get height {}", plaintext_metadata))?
+        .parse::<i32>()
+        .map_err(|e| format!("failed to parse {} {}", e, plaintext_metadata))?;
+    let (frames, per_second) = ffprobe_properties
+        .get("r_frame_rate")
+        .ok_or(format!("failed to get frame rate {}", plaintext_metadata))?
+        .split_once('/')
+        .ok_or(format!("failed to parse fps {}", plaintext_metadata))?;
+    let fps = (frames
+        .parse::<f64>()
+        .map_err(|e| format!("failed to parse {} {}", e, plaintext_metadata))?)
+        / (per_second
+            .parse::<f64>()
+            .map_err(|e| format!("failed to parse {} {}", e, plaintext_metadata))?);
+    let duration_secs = ffprobe_properties
+        .get("duration")
+        .ok_or(format!("failed to get duration {}", plaintext_metadata))?
+        .parse::<Seconds>()
+        .map_err(|e| format!("failed to parse {} {}", e, plaintext_metadata))?;
+
+    log!("{} {} {} {}", width, height, fps, duration_secs);
+
+    return Ok(VideoMetadata {
+        width_px: width,
+        height_px: height,
+        fps: fps,
+        seconds_per_frame: 1.0 / fps,
+        duration_secs: duration_secs,
+    });
+}
+
+// --- EXTENDED METADATA --- //
+
+// everything else worth knowing about a video, for `vic probe` and the info panel.
+// VideoMetadata stays small, since it's all that playback and cutting need
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtendedMetadata {
+    pub basics: VideoMetadata,
+    pub container: String,                  // like "QuickTime / MOV"
+    pub codec: String,                      // like "h264 (High)"
+    pub pixel_format: String,               // like "yuv420p"
+    pub bitrate: Option<u64>,               // bits per second, all streams together
+    pub video_bitrate: Option<u64>,         // bits per second, just the video stream
+    pub rotation: i32, // degrees clockwise that players turn the video, 0 to 270
+    pub audio_streams: Vec<String>, // like "aac, stereo, 48000 Hz, eng"
+    pub subtitle_streams: Vec<String>, // like "subrip, eng"
+    pub frame_count: Option<u64>, // from the container, which doesn't always know
+    pub keyframe_interval: Option<Seconds>, // average, over the first KEYFRAME_SAMPLE_SECS
+    pub variable_framerate: bool,
+}
+
+// reading every packet of a long video would take a while,
+// so keyframes and frame timing are only sampled from the start
+const KEYFRAME_SAMPLE_SECS: u32 = 30;
+
+pub fn get_ffprobe_extended_metadata(video_filepath: &str) -> Result<ExtendedMetadata, VicError> {
+    // two ffprobe runs, both with json output:
+    // every stream and the container, then the video packets at the start.
+    // one run can't do both, since selecting the video stream for packets hides the other streams
+    let mut streams_cmd = std::process::Command::new("ffprobe");
+    streams_cmd
+        .args(["-v", "error"])
+        .args(["-show_format", "-show_streams"])
+        .args(["-print_format", "json"])
+        .arg(video_filepath);
+    let streams_json = _ffprobe_output(&mut streams_cmd)?;
+
+    let mut packets_cmd = std::process::Command::new("ffprobe");
+    packets_cmd
+        .args(["-v", "error"])
+        .args(["-select_streams", "v:0"])
+        .args(["-show_entries", "packet=pts_time,flags"])
+        .args(["-read_intervals", &format!("%+{}", KEYFRAME_SAMPLE_SECS)])
+        .args(["-print_format", "json"])
+        .arg(video_filepath);
+    let packets_json = _ffprobe_output(&mut packets_cmd)?;
+
+    return parse_ffprobe_json(&streams_json, &packets_json).map_err(VicError::UnsupportedStream);
+}
+
+pub fn parse_ffprobe_json(
+    streams_json: &str,
+    packets_json: &str,
+) -> Result<ExtendedMetadata, String> {
+    // given `ffprobe -show_format -show_streams -print_format json`:
+    // ```
+    // {
+    //   "streams": [
+    //     { "codec_type": "video", "codec_name": "h264", "width": 1920, "r_frame_rate": "30/1", ... },
+    //     { "codec_type": "audio", "codec_name": "aac", "channels": 2, "sample_rate": "48000", ... }
+    //   ],
+    //   "format": { "format_long_name": "QuickTime / MOV", "duration": "10.000000", ... }
+    // }
+    // ```
+    //
+    // and the video packets, like `{ "packets": [{ "pts_time": "0.000000", "flags": "K__" }, ...] }`
+    //
+    // note that ffprobe writes many numbers as strings
+    let probe =
+        json::parse(streams_json).map_err(|e| format!("failed to parse ffprobe json. {}", e))?;
+    let packets =
+        json::parse(packets_json).map_err(|e| format!("failed to parse ffprobe json. {}", e))?;
+
+    let streams = probe
+        .get("streams")
+        .and_then(Value::as_array)
+        .ok_or("ffprobe found no streams")?;
+    let streams_of_type = |codec_type: &'static str| {
+        streams
+            .iter()
+            .filter(move |stream| _str_field(stream, "codec_type") == Some(codec_type))
+    };
+    let video = streams_of_type("video")
+        .next()
+        .ok_or("no video stream found")?;
+    let format = probe
+        .get("format")
+        .ok_or("ffprobe found no container format")?;
+
+    let width = _number_field(video, "width").ok_or("failed to get width")? as i32;
+    let height = _number_field(video, "height").ok_or("failed to get height")? as i32;
+    let fps = _str_field(video, "r_frame_rate")
+        .and_then(_parse_fraction)
+        .ok_or("failed to get frame rate")?;
+    let duration_secs = _number_field(format, "duration").ok_or("failed to get duration")?;
+
+    // phones record sideways and tag the video to be turned when played.
+    // newer ffprobes report a display matrix, turning the other way, and older ones a rotate tag
+    let display_matrix_rotation = video
+        .get("side_data_list")
+        .and_then(Value::as_array)
+        .and_then(|side_data| {
+            side_data
+                .iter()
+                .find_map(|data| _number_field(data, "rotation"))
+        });
+    let rotation = match display_matrix_rotation {
+        Some(degrees) => -degrees,
+        None => video
+            .get("tags")
+            .and_then(|tags| _number_field(tags, "rotate"))
+            .unwrap_or(0.0),
+    };
+
+    let audio_streams = streams_of_type("audio")
+        .map(|stream| {
+            let channels = match (
+                _str_field(stream, "channel_layout"),
+                _number_field(stream, "channels"),
+            ) {
+                (Some(layout), _) => Some(layout.to_string()),
+                (None, Some(channels)) if channels == 1.0 => Some("mono".to_string()),
+                (None, Some(channels)) => Some(format!("{} channels", channels)),
+                (None, None) => None,
+            };
+            let parts = [
+                _str_field(stream, "codec_name").map(str::to_string),
+                channels,
+                _number_field(stream, "sample_rate").map(|hz| format!("{} Hz", hz)),
+                stream
+                    .get("tags")
+                    .and_then(|tags| _str_field(tags, "language"))
+                    .map(str::to_string),
+            ];
+            parts
+                .into_iter()
+                .flatten()
+                .collect::<Vec<String>>()
+                .join(", ")
+        })
+        .collect();
+    let subtitle_streams = streams_of_type("subtitle")
+        .map(|stream| {
+            let tags = stream.get("tags");
+            let parts = [
+                _str_field(stream, "codec_name"),
+                tags.and_then(|tags| _str_field(tags, "language")),
+                tags.and_then(|tags| _str_field(tags, "title")),
+            ];
+            parts
+                .into_iter()
+                .flatten()
+                .collect::<Vec<&str>>()
+                .join(", ")
+        })
+        .collect();
+
+    // --- keyframes and frame timing, from the packets --- //
+
+    let packets = packets
+        .get("packets")
+        .and_then(Value::as_array)
+        .map(|packets| packets.as_slice())
+        .unwrap_or(&[]);
+    let mut keyframe_times = packets
+        .iter()
+        .filter(|packet| _str_field(packet, "flags").is_some_and(|flags| flags.contains('K')))
+        .filter_map(|packet| _number_field(packet, "pts_time"))
+        .filter(|time| time.is_finite()) // "nan" parses too
+        .collect::<Vec<Seconds>>();
+    keyframe_times.sort_by(f64::total_cmp);
+    let keyframe_interval = match keyframe_times.len() {
+        0 | 1 => None,
+        n => Some((keyframe_times[n - 1] - keyframe_times[0]) / (n - 1) as f64),
+    };
+
+    // packets are in decoding order, which isn't display order when there are b-frames
+    let mut frame_times = packets
+        .iter()
+        .filter_map(|packet| _number_field(packet, "pts_time"))
+        .filter(|time| time.is_finite())
+        .collect::<Vec<Seconds>>();
+    frame_times.sort_by(f64::total_cmp);
+    let frame_gaps = frame_times
+        .windows(2)
+        .map(|pair| pair[1] - pair[0])
+        .filter(|gap| *gap > 0.0)
+        .collect::<Vec<Seconds>>();
+    // timestamps are rounded to the container's timebase, so allow some jitter,
+    // like 29.97fps in mkv alternating between 33ms and 34ms
+    let shortest_gap = frame_gaps.iter().copied().fold(f64::INFINITY, f64::min);
+    let longest_gap = frame_gaps.iter().copied().fold(0.0, f64::max);
+    let has_uneven_frames = frame_gaps.len() >= 2 && longest_gap > shortest_gap * 1.5;
+    // the average framerate also drifts from the nominal one when frames are uneven
+    let has_different_average_fps =
+        match _str_field(video, "avg_frame_rate").and_then(_parse_fraction) {
+            Some(avg_fps) => (avg_fps - fps).abs() / fps > 0.01,
+            None => false,
+        };
+
+    return Ok(ExtendedMetadata {
+        basics: VideoMetadata {
+            width_px: width,
+            height_px: height,
+            fps: fps,
+            seconds_per_frame: 1.0 / fps,
+            duration_secs: duration_secs,
+        },
+        container: _str_field(format, "format_long_name")
+            .or(_str_field(format, "format_name"))
+            .unwrap_or("unknown")
+            .to_string(),
+        codec: match (
+            _str_field(video, "codec_name"),
+            _str_field(video, "profile"),
+        ) {
+            (Some(codec), Some(profile)) => format!("{} ({})", codec, profile),
+            (Some(codec), None) => codec.to_string(),
+            (None, _) => "unknown".to_string(),
+        },
+        pixel_format: _str_field(video, "pix_fmt")
+            .unwrap_or("unknown")
+            .to_string(),
+        bitrate: _number_field(format, "bit_rate").map(|bps| bps as u64),
+        video_bitrate: _number_field(video, "bit_rate").map(|bps| bps as u64),
+        rotation: (rotation as i32).rem_euclid(360),
+        audio_streams: audio_streams,
+        subtitle_streams: subtitle_streams,
+        frame_count: _number_field(video, "nb_frames")
+            .map(|n| n as u64)
+            .filter(|n| *n > 0),
+        keyframe_interval: keyframe_interval,
+        variable_framerate: has_uneven_frames || has_different_average_fps,
+    });
+}
+
+fn _str_field<'a>(value: &'a Value, key: &str) -> Option<&'a str> {
+    return value.get(key).and_then(Value::as_str);
+}
+
+fn _number_field(value: &Value, key: &str) -> Option<f64> {
+    // like "width": 1920, or "bit_rate": "1205959"
+    return match value.get(key)? {
+        Value::Number(n) => Some(*n),
+        Value::Str(s) => s.parse::<f64>().ok(),
+        _ => None,
+    };
+}
+
+fn _parse_fraction(fraction: &str) -> Option<f64> {
+    // like 30000/1001. ffprobe says 0/0 when it doesn't know
+    let (numerator, denominator) = fraction.split_once('/')?;
+    let value = numerator.parse::<f64>().ok()? / denominator.parse::<f64>().ok()?;
+    return match value.is_finite() && value > 0.0 {
+        true => Some(value),
+        false => None,
+    };
+}
+
+impl ExtendedMetadata {
+    pub fn describe(&self) -> Vec<String> {
+        // one line per detail, like `codec ......... h264 (High)`,
+        // for `vic probe` and the info panel
+        let estimated_frames = (self.basics.duration_secs * self.basics.fps).round();
+        let rows = [
+            vec![("container", self.container.clone())],
+            vec![(
+                "size",
+                format!("{}x{}", self.basics.width_px, self.basics.height_px),
+            )],
+            vec![("duration", format!("{:.3} secs", self.basics.duration_secs))],
+            vec![("codec", self.codec.clone())],
+            vec![("pixel format", self.pixel_format.clone())],
+            vec![(
+                "framerate",
+                format!(
+                    "{} fps, {}",
+                    _format_number(self.basics.fps),
+                    match self.variable_framerate {
+                        true => "variable",
+                        false => "constant",
+                    }
+                ),
+            )],
+            vec![(
+                "frames",
+                match self.frame_count {
+                    Some(frame_count) => frame_count.to_string(),
+                    None => format!("about {}", estimated_frames),
+                },
+            )],
+            vec![(
+                "keyframes",
+                match self.keyframe_interval {
+                    Some(interval) => format!("every {} secs", _format_number(interval)),
+                    None => format!("fewer than 2 in the first {} secs", KEYFRAME_SAMPLE_SECS),
+                },
+            )],
+            vec![(
+                "bitrate",
+                match (self.bitrate, self.video_bitrate) {
+                    (Some(total), Some(video)) => {
+                        format!("{} kb/s, {} kb/s video", total / 1000, video / 1000)
+                    }
+                    (Some(total), None) => format!("{} kb/s", total / 1000),
+                    (None, Some(video)) => format!("{} kb/s video", video / 1000),
+                    (None, None) => "unknown".to_string(),
+                },
+            )],
+            vec![("rotation", format!("{}°", self.rotation))],
+            _describe_streams("audio", &self.audio_streams),
+            _describe_streams("subtitles", &self.subtitle_streams),
+        ];
+        return rows
+            .concat()
+            .into_iter()
+            .map(|(label, value)| match label {
+                "" => format!("{:15} {}", "", value),
+                _ => format!("{} {} {}", label, ".".repeat(14 - label.len()), value),
+            })
+            .collect();
+    }
+
+    pub(crate) fn to_json(&self) -> Value {
+        let optional_number = |n: Option<f64>| match n {
+            Some(n) => Value::Number(n),
+            None => Value::Null,
+        };
+        let strings = |strings: &[String]| {
+            Value::Array(strings.iter().map(|s| Value::Str(s.clone())).collect())
+        };
+        return Value::object(vec![
+            ("width", Value::Number(self.basics.width_px as f64)),
+            ("height", Value::Number(self.basics.height_px as f64)),
+            ("fps", Value::Number(self.basics.fps)),
+            ("duration", Value::Number(self.basics.duration_secs)),
+            ("container", Value::Str(self.container.clone())),
+            ("codec", Value::Str(self.codec.clone())),
+            ("pixel_format", Value::Str(self.pixel_format.clone())),
+            (
+                "bitrate",
+                optional_number(self.bitrate.map(|bps| bps as f64)),
+            ),
+            (
+                "video_bitrate",
+                optional_number(self.video_bitrate.map(|bps| bps as f64)),
+            ),
+            ("rotation", Value::Number(self.rotation as f64)),
+            ("audio_streams", strings(&self.audio_streams)),
+            ("subtitle_streams", strings(&self.subtitle_streams)),
+            (
+                "frame_count",
+                optional_number(self.frame_count.map(|n| n as f64)),
+            ),
+            ("keyframe_interval", optional_number(self.keyframe_interval)),
+            ("variable_framerate", Value::Bool(self.variable_framerate)),
+        ]);
+    }
+}
+
+fn _describe_streams(label: &'static str, streams: &[String]) -> Vec<(&'static str, String)> {
+    // one row per stream, with the label only on the first
+    return match streams.len() {
+        0 => vec![(label, "none".to_string())],
+        _ => streams
+            .iter()
+            .enumerate()
+            .map(|(i, stream)| match i {
+                0 => (label, stream.clone()),
+                _ => ("", stream.clone()),
+            })
+            .collect(),
+    };
+}
+
+fn _format_number(n: f64) -> String {
+    // like 30, 29.97, or 2.5, without trailing zeros
+    let text = format!("{:.3}", n);
+    return text.trim_end_matches('0').trim_end_matches('.').to_string();
+}
+
+#[cfg(test)]
+#[test]
+fn test_parsing_ffprobe_output_with_crlf() {
+    // i would use literal  instead of \r for closer fidelity to actual scenario
+    // but rust tooling didn't like that
+    let sample_ffprobe_output_with_windows_newline =
+        "width=1920,height=1080,r_frame_rate=60/1\r\nduration=1246.333333".to_string();
+
+    match _parse_ffprobe_output(sample_ffprobe_output_with_windows_newline) {
+        Ok(metadata) => assert_eq!(
+            metadata,
+            VideoMetadata {
+                width_px: 1920,
+                height_px: 1080,
+                fps: 60.0,
+                seconds_per_frame: 1.0 / 60.0,
+                duration_secs: 1246.333333
+            }
+        ),
+        Err(e) => panic!("{}", e),
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_parsing_ffprobe_json_from_a_phone_video() {
+    // trimmed from a real ffprobe of a sideways phone video, with variable framerate
+    let streams_json = r#"{
+        "streams": [
+            {
+                "index": 0, "codec_name": "h264", "profile": "High", "codec_type": "video",
+                "width": 1920, "height": 1080, "pix_fmt": "yuv420p",
+                "r_frame_rate": "30/1", "avg_frame_rate": "5400000/182443",
+                "bit_rate": "16998912", "nb_frames": "180",
+                "tags": { "rotate": "90", "language": "und" },
+                "side_data_list": [ { "side_data_type": "Display Matrix", "rotation": -90 } ]
+            },
+            {
+                "index": 1, "codec_name": "aac", "codec_type": "audio",
+                "sample_rate": "48000", "channels": 2, "channel_layout": "stereo",
+                "tags": { "language": "eng" }
+            },
+            { "index": 2, "codec_name": "opus", "codec_type": "audio", "sample_rate": "48000", "channels": 1 },
+            { "index": 3, "codec_name": "mov_text", "codec_type": "subtitle", "tags": { "language": "eng", "title": "captions" } }
+        ],
+        "format": {
+            "format_name": "mov,mp4,m4a,3gp,3g2,mj2", "format_long_name": "QuickTime / MOV",
+            "duration": "6.081000", "bit_rate": "17205480"
+        }
+    }"#;
+    let packets_json = r#"{
+        "packets": [
+            { "pts_time": "0.000000", "flags": "K__" },
+            { "pts_time": "0.066667", "flags": "___" },
+            { "pts_time": "0.033333", "flags": "___" },
+            { "pts_time": "0.150000", "flags": "___" },
+            { "pts_time": "2.000000", "flags": "K__" },
+            { "pts_time": "4.000000", "flags": "K__" }
+        ]
+    }"#;
+
+    let details = parse_ffprobe_json(streams_json, packets_json).unwrap();
+    assert_eq!(details.basics.width_px, 1920);
+    assert_eq!(details.basics.fps, 30.0);
+    assert_eq!(details.rotation, 90);
+    assert_eq!(details.keyframe_interval, Some(2.0));
+    assert!(details.variable_framerate);
+    assert_eq!(
+        details.describe(),
+        vec![
+            "container ..... QuickTime / MOV",
+            "size .......... 1920x1080",
+            "duration ...... 6.081 secs",
+            "codec ......... h264 (High)",
+            "pixel format .. yuv420p",
+            "framerate ..... 30 fps, variable",
+            "frames ........ 180",
+            "keyframes ..... every 2 secs",
+            "bitrate ....... 17205 kb/s, 16998 kb/s video",
+            "rotation ...... 90°",
+            "audio ......... aac, stereo, 48000 Hz, eng",
+            "                opus, mono, 48000 Hz",
+            "subtitles ..... mov_text, eng, captions",
+        ]
+    );
+    assert_eq!(
+        json::parse(&details.to_json().to_string()).unwrap(),
+        details.to_json()
+    );
+
+    // garbled timestamps are skipped
+    let garbled_packets_json = packets_json.replace(
+        r#""pts_time": "0.150000", "flags": "___""#,
+        r#""pts_time": "nan", "flags": "K__""#,
+    );
+    let details = parse_ffprobe_json(streams_json, &garbled_packets_json).unwrap();
+    assert_eq!(details.keyframe_interval, Some(2.0));
+}
@@ -0,0 +1,259 @@
+// the --dry-run recipe: export commands written out as text instead of run
+//
+// formats:
+//   plain .. one indented command per line, for reading
+//   sh ..... a runnable POSIX shell script
+//   json ... an array of argv arrays, for other programs
+//   bat .... a runnable windows batch file
+
+use std::ffi::OsStr;
+use std::process::Command;
+
+use crate::json;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DryRunFormat {
+    Plain,
+    Sh,
+    Json,
+    Bat,
+}
+
+impl std::str::FromStr for DryRunFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "plain" => Ok(DryRunFormat::Plain),
+            "sh" => Ok(DryRunFormat::Sh),
+            "json" => Ok(DryRunFormat::Json),
+            "bat" => Ok(DryRunFormat::Bat),
+            _ => Err(format!(
+                "unknown dry run format {}\nexpected one of plain, sh, json, bat",
+                s
+            )),
+        }
+    }
+}
+
+pub fn format_recipe(cmds: &[&Command], format: DryRunFormat) -> String {
+    return match format {
+        DryRunFormat::Plain => cmds
+            .iter()
+            .map(|cmd| format!("  {}", cmd_to_sh(cmd)))
+            .collect::<Vec<String>>()
+            .join(";\n"),
+        DryRunFormat::Sh => {
+            let mut script = "#!/bin/sh\nset -e\n\n".to_string();
+            for cmd in cmds {
+                script.push_str(&cmd_to_sh(cmd));
+                script.push('\n');
+            }
+            script
+        }
+        DryRunFormat::Json => {
+            let argvs = cmds
+                .iter()
+                .map(|cmd| {
+                    let argv = argv(cmd)
+                        .map(|arg| json::quote(&arg.to_string_lossy()))
+                        .collect::<Vec<String>>()
+                        .join(", ");
+                    format!("  [{}]", argv)
+                })
+                .collect::<Vec<String>>()
+                .join(",\n");
+            match cmds.len() {
+                0 => "[]".to_string(),
+                _ => format!("[\n{}\n]", argvs),
+            }
+        }
+        DryRunFormat::Bat => {
+            // stop at the first failure, like set -e
+            let mut script = "@echo off\r\n\r\n".to_string();
+            for cmd in cmds {
+                script.push_str(&cmd_to_bat(cmd));
+                script.push_str("\r\nif errorlevel 1 exit /b %errorlevel%\r\n");
+            }
+            script
+        }
+    };
+}
+
+fn argv(cmd: &Command) -> impl Iterator<Item = &OsStr> {
+    return std::iter::once(cmd.get_program()).chain(cmd.get_args());
+}
+
+pub fn cmd_to_sh(cmd: &Command) -> String {
+    // example:
+    // Command("ffmpeg" "-ss" "60.250" "-i" "./my file.mp4" "./it's_0.mp4")
+    // becomes
+    // ffmpeg -ss 60.250 -i './my file.mp4' './it'\''s_0.mp4'
+    return argv(cmd).map(sh_quote).collect::<Vec<String>>().join(" ");
+}
+
+pub fn sh_quote(arg: &OsStr) -> String {
+    // POSIX shells take everything inside single quotes literally, except a single quote itself,
+    // which has to close the quotes, be escaped, then reopen the quotes: it's -> 'it'\''s'
+    //
+    // bytes that aren't UTF-8 can't go in the String, so they come from printf instead,
+    // as octal escapes like "$(printf '\377')"
+    let bytes = os_str_bytes(arg);
+    let is_plain = !bytes.is_empty()
+        && bytes
+            .iter()
+            .all(|b| b.is_ascii_alphanumeric() || b"-_+=:.,/@%".contains(b));
+    if is_plain {
+        return String::from_utf8_lossy(&bytes).to_string();
+    }
+
+    let mut quoted = String::new();
+    let mut rest = &bytes[..];
+    while !rest.is_empty() {
+        let (valid, invalid) = match std::str::from_utf8(rest) {
+            Ok(valid) => (valid, &rest[rest.len()..]),
+            Err(e) => {
+                let (valid, after) = rest.split_at(e.valid_up_to());
+                let num_invalid = e.error_len().unwrap_or(after.len());
+                (std::str::from_utf8(valid).unwrap(), &after[..num_invalid])
+            }
+        };
+        if !valid.is_empty() {
+            quoted.push_str(&format!("'{}'", valid.replace('\'', "'\\''")));
+        }
+        if !invalid.is_empty() {
+            let octals = invalid
+                .iter()
+                .map(|b| format!("\\{:03o}", b))
+                .collect::<String>();
+            quoted.push_str(&format!("\"$(printf '{}')\"", octals));
+        }
+        rest = &rest[valid.len() + invalid.len()..];
+    }
+    return match quoted.is_empty() {
+        true => "''".to_string(),
+        false => quoted,
+    };
+}
+
+#[cfg(unix)]
+fn os_str_bytes(arg: &OsStr) -> Vec<u8> {
+    use std::os::unix::ffi::OsStrExt;
+    return arg.as_bytes().to_vec();
+}
+
+#[cfg(not(unix))]
+fn os_str_bytes(arg: &OsStr) -> Vec<u8> {
+    return arg.to_string_lossy().as_bytes().to_vec();
+}
+
+fn cmd_to_bat(cmd: &Command) -> String {
+    return argv(cmd).map(bat_quote).collect::<Vec<String>>().join(" ");
+}
+
+fn bat_quote(arg: &OsStr) -> String {
+    // programs on windows split their own command line, usually like the C runtime does:
+    // double quotes group words, and \" is a literal quote.
+    // then batch files expand %vars% before anything else, so a literal % has to be %%,
+    // even in plain args like ffmpeg's image sequence pattern, frame_%05d.png
+    let arg = arg.to_string_lossy();
+    let is_plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_+=:.,/\\@%".contains(c));
+    let quoted = match is_plain {
+        true => arg.to_string(),
+        false => {
+            // backslashes are only special right before a quote
+            let mut quoted = "\"".to_string();
+            let mut num_backslashes = 0;
+            for c in arg.chars() {
+                match c {
+                    '\\' => num_backslashes += 1,
+                    '"' => {
+                        quoted.push_str(&"\\".repeat(num_backslashes * 2 + 1));
+                        num_backslashes = 0;
+                    }
+                    _ => {
+                        quoted.push_str(&"\\".repeat(num_backslashes));
+                        num_backslashes = 0;
+                    }
+                }
+                if c != '\\' {
+                    quoted.push(c);
+                }
+            }
+            quoted.push_str(&"\\".repeat(num_backslashes * 2));
+            quoted.push('"');
+            quoted
+        }
+    };
+    // cmd.exe also reads the line first, with its own quoting that flips at every ",
+    // even an escaped \". outside of its quotes, operators like & need a ^ to stay literal
+    let mut in_cmd_quotes = false;
+    let mut escaped = String::new();
+    for c in quoted.chars() {
+        match c {
+            '"' => in_cmd_quotes = !in_cmd_quotes,
+            '&' | '|' | '<' | '>' | '(' | ')' | '^' if !in_cmd_quotes => escaped.push('^'),
+            _ => (),
+        }
+        escaped.push(c);
+    }
+    return escaped.replace('%', "%%");
+}
+
+// make sure the stringifying above does not accidentally break in the future
+#[cfg(test)]
+#[test]
+fn test_ffmpeg_commands_are_properly_stringified() {
+    let mut sample_cmd = Command::new("ffmpeg");
+    sample_cmd
+        .arg("-ss")
+        .arg(format!("{:.3}", 60))
+        .arg("-to")
+        .arg(format!("{:.3}", 123.456789))
+        .arg("-i")
+        .arg("./filename/with spaces.mp4")
+        .arg("./filename/with spaces_0.mp4");
+    assert_eq!(
+        cmd_to_sh(&sample_cmd),
+        "ffmpeg -ss 60 -to 123.457 -i './filename/with spaces.mp4' './filename/with spaces_0.mp4'"
+    );
+
+    let mut sample_cmd_with_preset = Command::new("ffmpeg");
+    sample_cmd_with_preset
+        .args(["-ss", "0.000", "-to", "1.500", "-i", "in.mp4"])
+        .args(["-c:v", "libx264", "-vf", "scale=640:-2, fps=24"])
+        .arg("out.mp4");
+    assert_eq!(
+        cmd_to_sh(&sample_cmd_with_preset),
+        "ffmpeg -ss 0.000 -to 1.500 -i in.mp4 -c:v libx264 -vf 'scale=640:-2, fps=24' out.mp4"
+    );
+
+    let mut sample_cmd_with_quotes = Command::new("ffmpeg");
+    sample_cmd_with_quotes
+        .args(["-i", "it's.mp4", "-metadata", "title=", ""])
+        .arg("say \"hi\" 100%.mp4");
+    assert_eq!(
+        cmd_to_sh(&sample_cmd_with_quotes),
+        r#"ffmpeg -i 'it'\''s.mp4' -metadata title= '' 'say "hi" 100%.mp4'"#
+    );
+    assert_eq!(
+        cmd_to_bat(&sample_cmd_with_quotes),
+        r#"ffmpeg -i "it's.mp4" -metadata title= "" "say \"hi\" 100%%.mp4""#
+    );
+    // after an escaped quote, cmd.exe thinks the quotes have ended
+    assert_eq!(bat_quote(OsStr::new("a & b")), r#""a & b""#);
+    assert_eq!(bat_quote(OsStr::new("a\"b & c")), r#""a\"b ^& c""#);
+    assert_eq!(bat_quote(OsStr::new("a\"(b|c)^")), r#""a\"^(b^|c^)^^""#);
+}
+
+#[cfg(all(test, unix))]
+#[test]
+fn test_non_utf8_args_are_quoted_with_printf() {
+    use std::os::unix::ffi::OsStrExt;
+    assert_eq!(
+        sh_quote(OsStr::from_bytes(b"caf\xe9 1.mp4")),
+        r#"'caf'"$(printf '\351')"' 1.mp4'"#
+    );
+}
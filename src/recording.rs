@@ -0,0 +1,106 @@
+// key recordings: every terminal event from a session, with its time since start,
+// for --record-keys and --replay-keys
+//
+// one event per line, like:
+//
+//   # comments and blank lines are skipped
+//   0.512 key space
+//   1.250 key ctrl+r
+//   3.000 resize 80 24
+//
+// keys use the same names as the [keys] section of the config file
+
+use crossterm::event::Event;
+
+use crate::keymap::Key;
+use crate::Seconds;
+
+pub const HEADER: &str = "# vic key recording: seconds since start, then the event\n";
+
+pub fn format_event(at: Seconds, event: &Event) -> Option<String> {
+    // None for events that vic doesn't use, like the mouse
+    let event_text = match event {
+        Event::Key(keyevent) => format!("key {}", Key::from_event(keyevent)),
+        Event::Resize(cols, rows) => format!("resize {} {}", cols, rows),
+        _ => return None,
+    };
+    return Some(format!("{:.3} {}", at, event_text));
+}
+
+pub fn parse(text: &str) -> Result<Vec<(Seconds, Event)>, String> {
+    let mut events = Vec::<(Seconds, Event)>::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let parse_line = || -> Result<(Seconds, Event), String> {
+            let fields = line.split_whitespace().collect::<Vec<&str>>();
+            let at = fields[0]
+                .parse::<Seconds>()
+                .map_err(|e| format!("bad time {}", fields[0]))?;
+            let event = match fields[1..] {
+                ["key", key] => Event::Key(key.parse::<Key>()?.to_event()),
+                ["resize", cols, rows] => Event::Resize(
+                    cols.parse()
+                        .map_err(|e| format!("bad column count {}", cols))?,
+                    rows.parse()
+                        .map_err(|e| format!("bad row count {}", rows))?,
+                ),
+                _ => return Err("expected an event like `key m` or `resize 80 24`".to_string()),
+            };
+            return Ok((at, event));
+        };
+        events.push(parse_line().map_err(|e| format!("line {}: {}", i + 1, e))?);
+    }
+    // replaying out of order would make time go backwards
+    if events.windows(2).any(|pair| pair[1].0 < pair[0].0) {
+        return Err("events must be in order of time".to_string());
+    }
+    return Ok(events);
+}
+
+#[cfg(test)]
+#[test]
+fn test_recorded_events_parse_back_the_same() {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    let events = vec![
+        (
+            0.5,
+            Event::Key(KeyEvent::new(KeyCode::Char(' '), KeyModifiers::NONE)),
+        ),
+        (
+            1.25,
+            Event::Key(KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL)),
+        ),
+        (
+            1.25,
+            Event::Key(KeyEvent::new(KeyCode::Char('J'), KeyModifiers::NONE)),
+        ),
+        (3.0, Event::Resize(80, 24)),
+        (
+            4.0,
+            Event::Key(KeyEvent::new(KeyCode::Left, KeyModifiers::NONE)),
+        ),
+    ];
+    let text = events
+        .iter()
+        .filter_map(|(at, event)| format_event(*at, event))
+        .map(|line| format!("{}\n", line))
+        .collect::<String>();
+    assert_eq!(
+        text,
+        "0.500 key space\n1.250 key ctrl+r\n1.250 key J\n3.000 resize 80 24\n4.000 key left\n"
+    );
+    assert_eq!(parse(&format!("{}\n{}", HEADER, text)), Ok(events));
+
+    assert_eq!(
+        parse("1.0 key m\n0.5 key m"),
+        Err("events must be in order of time".to_string())
+    );
+    assert_eq!(
+        parse("0.5 press m"),
+        Err("line 1: expected an event like `key m` or `resize 80 24`".to_string())
+    );
+}
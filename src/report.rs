@@ -0,0 +1,258 @@
+// --print: the edits from a session, written to stdout on finish,
+// for other tools to read. the TUI is drawn on stderr, so stdout stays clean
+//
+// formats:
+//   markers ... one marker timestamp per line, in seconds
+//   segments .. one segment per line: start, end, kept or discarded, and label, tab-separated
+//   json ...... markers, segments, and ranges, with each output path
+
+use std::path::PathBuf;
+
+use crate::export::{self, ExportOptions};
+use crate::json::Value;
+use crate::timeline::{self, ClipRange, Segment};
+use crate::Seconds;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PrintFormat {
+    Markers,
+    Segments,
+    Json,
+}
+
+impl std::str::FromStr for PrintFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "markers" => Ok(PrintFormat::Markers),
+            "segments" => Ok(PrintFormat::Segments),
+            "json" => Ok(PrintFormat::Json),
+            _ => Err(format!(
+                "unknown print format {}\nexpected one of markers, segments, json",
+                s
+            )),
+        }
+    }
+}
+
+pub fn format_report(
+    format: PrintFormat,
+    video_path: &str,
+    duration_secs: Seconds,
+    markers: &[Seconds],
+    segments: &[Segment],
+    ranges: &[ClipRange],
+    options: &ExportOptions,
+) -> Result<String, String> {
+    // fails for markers and segments that don't line up, or for json,
+    // when an output filename can't be made, like from an output template
+    // with {label} and an unlabeled segment
+    timeline::check_markers(markers, segments)?;
+
+    // segment i spans from the marker before it to the marker after it
+    //
+    // segment    0     1     2
+    //         ┌─────┬─────┬──────┐
+    //         └─────┴─────┴──────┘
+    // marker        0     1
+    //
+    let timestamps = std::iter::once(0.)
+        .chain(markers.iter().copied())
+        .chain(std::iter::once(duration_secs))
+        .collect::<Vec<Seconds>>();
+
+    return Ok(match format {
+        PrintFormat::Markers => markers
+            .iter()
+            .map(|timestamp| format!("{:.3}\n", timestamp))
+            .collect(),
+        PrintFormat::Segments => segments
+            .iter()
+            .enumerate()
+            .map(|(i, segment)| {
+                format!(
+                    "{:.3}\t{:.3}\t{}\t{}\n",
+                    timestamps[i],
+                    timestamps[i + 1],
+                    match segment.kept {
+                        true => "kept",
+                        false => "discarded",
+                    },
+                    segment.label
+                )
+            })
+            .collect(),
+        PrintFormat::Json => {
+            let (segment_outpaths, range_outpaths) = outpaths(
+                video_path,
+                duration_secs,
+                markers,
+                segments,
+                ranges,
+                options,
+            )?;
+            let path_json = |outpath: &Option<PathBuf>| match outpath {
+                Some(outpath) => Value::Str(outpath.display().to_string()),
+                None => Value::Null,
+            };
+            let segments_json = segments
+                .iter()
+                .enumerate()
+                .map(|(i, segment)| {
+                    let (start, end) = (timestamps[i], timestamps[i + 1]);
+                    Value::object(vec![
+                        ("start", Value::Number(start)),
+                        ("end", Value::Number(end)),
+                        ("duration", Value::Number(end - start)),
+                        ("kept", Value::Bool(segment.kept)),
+                        ("label", Value::Str(segment.label.clone())),
+                        ("output", path_json(&segment_outpaths[i])),
+                    ])
+                })
+                .collect();
+            let ranges_json = ranges
+                .iter()
+                .enumerate()
+                .map(|(i, range)| {
+                    Value::object(vec![
+                        ("start", Value::Number(range.start)),
+                        ("end", Value::Number(range.end)),
+                        ("duration", Value::Number(range.end - range.start)),
+                        ("label", Value::Str(range.name.clone())),
+                        ("output", path_json(&range_outpaths[i])),
+                    ])
+                })
+                .collect();
+            let report = Value::object(vec![
+                ("video", Value::Str(video_path.to_string())),
+                ("duration", Value::Number(duration_secs)),
+                (
+                    "markers",
+                    Value::Array(markers.iter().map(|t| Value::Number(*t)).collect()),
+                ),
+                ("segments", Value::Array(segments_json)),
+                ("ranges", Value::Array(ranges_json)),
+            ]);
+            format!("{}\n", report.pretty())
+        }
+    });
+}
+
+type Outpaths = Vec<Option<PathBuf>>; // one per segment or range
+
+fn outpaths(
+    video_path: &str,
+    duration_secs: Seconds,
+    markers: &[Seconds],
+    segments: &[Segment],
+    ranges: &[ClipRange],
+    options: &ExportOptions,
+) -> Result<(Outpaths, Outpaths), String> {
+    // where each segment and range gets exported, if at all.
+    // discarded segments have no output, and neither does the whole video when there are no markers
+    let mut segment_outpaths = vec![None; segments.len()];
+    let mut range_outpaths = vec![None; ranges.len()];
+    let cuts = export::collect_cuts(
+        video_path,
+        duration_secs,
+        markers,
+        segments,
+        ranges,
+        options,
+    )?;
+    let num_segment_cuts = match markers.len() {
+        0 => 0,
+        _ => segments.iter().filter(|segment| segment.kept).count(),
+    };
+    for (i, cut) in cuts.into_iter().enumerate() {
+        // everything goes into one audio file with --concat-audio
+        let outpath = match options.concat_audio {
+            true => match export::output_dir_and_stem(video_path, options) {
+                Ok((outdir, stem)) => export::concat_audio_path(&outdir, &stem, options),
+                Err(_) => continue, // can't happen once collect_cuts() worked
+            },
+            false => cut.outpath,
+        };
+        match i < num_segment_cuts {
+            true => segment_outpaths[cut.index] = Some(outpath),
+            false => range_outpaths[cut.index] = Some(outpath),
+        }
+    }
+    return Ok((segment_outpaths, range_outpaths));
+}
+
+#[cfg(test)]
+#[test]
+fn test_printing_segments_with_their_outputs() {
+    let markers = vec![5.0, 20.5];
+    let segments = vec![
+        Segment {
+            kept: true,
+            label: "intro".to_string(),
+        },
+        Segment {
+            kept: false,
+            label: "".to_string(),
+        },
+        Segment {
+            kept: true,
+            label: "".to_string(),
+        },
+    ];
+    let ranges = vec![ClipRange {
+        name: "range1".to_string(),
+        start: 1.0,
+        end: 2.0,
+    }];
+    let report_with = |format, options: &ExportOptions| {
+        format_report(
+            format,
+            "dir/video.mp4",
+            60.0,
+            &markers,
+            &segments,
+            &ranges,
+            options,
+        )
+    };
+    let report = |format| report_with(format, &ExportOptions::default()).unwrap();
+
+    assert_eq!(report(PrintFormat::Markers), "5.000\n20.500\n");
+    assert_eq!(
+        report(PrintFormat::Segments),
+        "0.000\t5.000\tkept\tintro\n5.000\t20.500\tdiscarded\t\n20.500\t60.000\tkept\t\n"
+    );
+    let json = crate::json::parse(&report(PrintFormat::Json)).unwrap();
+    let outputs = json
+        .get("segments")
+        .unwrap()
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|segment| segment.get("output").unwrap().clone())
+        .collect::<Vec<Value>>();
+    assert_eq!(
+        outputs,
+        vec![
+            Value::Str("dir/video_0.mp4".to_string()),
+            Value::Null,
+            Value::Str("dir/video_2.mp4".to_string()),
+        ]
+    );
+    assert_eq!(
+        json.get("segments").unwrap().as_array().unwrap()[2].get("duration"),
+        Some(&Value::Number(39.5))
+    );
+    assert_eq!(
+        json.get("ranges").unwrap().as_array().unwrap()[0].get("output"),
+        Some(&Value::Str("dir/video_range1.mp4".to_string()))
+    );
+
+    // outputs that can't be named are an error, not a null
+    let options = ExportOptions {
+        output_template: Some("{label}".to_string()),
+        ..Default::default()
+    };
+    assert!(report_with(PrintFormat::Json, &options).is_err());
+    assert!(report_with(PrintFormat::Segments, &options).is_ok());
+}
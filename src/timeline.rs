@@ -0,0 +1,193 @@
+// the edit state: markers, the segments between them, and in/out ranges
+//
+//   0:00          marker          marker          end
+//    |  segment 0    |  segment 1    |  segment 2   |
+//            [ range1 ]
+//                      [    range2     ]
+//
+// markers stay sorted, and there's always one more segment than markers.
+// ranges are independent of markers, and may overlap
+
+use crate::Seconds;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Segment {
+    pub kept: bool,    // else discarded when cutting on finish
+    pub label: String, // optional name, usable in output filenames
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClipRange {
+    pub name: String, // used in output filename, eg. video_range1.mp4
+    pub start: Seconds,
+    pub end: Seconds,
+}
+
+// markers and segments must stay in sync, since n markers make n+1 segments:
+//
+// segment    0     1     2
+//         ┌─────┬─────┬──────┐
+//         └─────┴─────┴──────┘
+// marker        0     1
+//
+// so only edit markers through these functions
+
+pub fn insert_marker(
+    markers: &mut Vec<Seconds>,
+    segments: &mut Vec<Segment>,
+    timestamp: Seconds,
+) -> usize {
+    // splitting a segment makes two segments with the same kept/discarded state and label.
+    // returns the new marker's index
+    // total_cmp() puts a NaN after everything else, rather than panicking
+    let pos = match markers.binary_search_by(|other| other.total_cmp(&timestamp)) {
+        Ok(pos) | Err(pos) => pos,
+    };
+    markers.insert(pos, timestamp);
+    segments.insert(pos, segments[pos].clone());
+    return pos;
+}
+
+pub fn remove_marker(markers: &mut Vec<Seconds>, segments: &mut Vec<Segment>, index: usize) {
+    // merging two segments keeps the state of the left one
+    markers.remove(index);
+    segments.remove(index + 1);
+}
+
+pub fn move_marker(
+    markers: &mut Vec<Seconds>,
+    segments: &mut Vec<Segment>,
+    index: usize,
+    new_timestamp: Seconds,
+) -> usize {
+    // replace a marker with a new timestamp, keeping markers sorted.
+    // returns the marker's new index, since it may have hopped over its neighbors
+    remove_marker(markers, segments, index);
+    return insert_marker(markers, segments, new_timestamp);
+}
+
+pub fn check_markers(markers: &[Seconds], segments: &[Segment]) -> Result<(), String> {
+    // for markers and segments that didn't come from the functions above, like from library users
+    if segments.len() != markers.len() + 1 {
+        return Err(format!(
+            "expected {} segments for {} markers, found {}",
+            markers.len() + 1,
+            markers.len(),
+            segments.len()
+        ));
+    }
+    if let Some(marker) = markers.iter().find(|marker| !marker.is_finite()) {
+        return Err(format!("marker at {} isn't a time", marker));
+    }
+    if markers.windows(2).any(|pair| pair[0] > pair[1]) {
+        return Err("markers aren't sorted".to_string());
+    }
+    return Ok(());
+}
+
+#[cfg(test)]
+fn kept_segment(kept: bool) -> Segment {
+    Segment {
+        kept: kept,
+        label: "".to_string(),
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_moving_marker_past_neighbors_keeps_markers_sorted() {
+    let mut markers: Vec<Seconds> = vec![1.0, 2.0, 3.0];
+    let mut segments = [true, false, true, true].map(kept_segment).to_vec();
+    assert_eq!(move_marker(&mut markers, &mut segments, 0, 2.5), 1);
+    assert_eq!(markers, vec![2.0, 2.5, 3.0]);
+    assert_eq!(move_marker(&mut markers, &mut segments, 2, 0.5), 0);
+    assert_eq!(markers, vec![0.5, 2.0, 2.5]);
+    assert_eq!(move_marker(&mut markers, &mut segments, 1, 2.1), 1);
+    assert_eq!(markers, vec![0.5, 2.1, 2.5]);
+    assert_eq!(segments.len(), markers.len() + 1);
+}
+
+#[cfg(test)]
+#[test]
+fn test_splitting_and_merging_segments_preserves_kept_state() {
+    let mut markers: Vec<Seconds> = vec![];
+    let mut segments = vec![kept_segment(true)];
+    insert_marker(&mut markers, &mut segments, 10.0);
+    segments[1].kept = false;
+    insert_marker(&mut markers, &mut segments, 20.0);
+    assert_eq!(segments, [true, false, false].map(kept_segment));
+    remove_marker(&mut markers, &mut segments, 0);
+    assert_eq!(markers, vec![20.0]);
+    assert_eq!(segments, [true, false].map(kept_segment));
+}
+
+#[cfg(test)]
+#[test]
+fn test_checking_markers_from_outside() {
+    let mut markers: Vec<Seconds> = vec![1.0, 2.0];
+    let mut segments = [true, false, true].map(kept_segment).to_vec();
+    assert_eq!(check_markers(&markers, &segments), Ok(()));
+    assert!(check_markers(&markers, &segments[..2]).is_err());
+    assert!(check_markers(&[2.0, 1.0], &segments).is_err());
+
+    // a NaN goes last instead of panicking, and is caught by the check
+    insert_marker(&mut markers, &mut segments, f64::NAN);
+    assert_eq!(markers.len(), 3);
+    assert!(markers[2].is_nan());
+    assert!(check_markers(&markers, &segments).is_err());
+}
+
+pub fn next_range_name(ranges: &[ClipRange]) -> String {
+    // range1, range2, ... skipping any names still in use,
+    // so output filenames never collide
+    let mut n = 1;
+    while ranges
+        .iter()
+        .any(|range| range.name == format!("range{}", n))
+    {
+        n += 1;
+    }
+    return format!("range{}", n);
+}
+
+pub fn assign_lanes(ranges: &[ClipRange]) -> Vec<usize> {
+    // stack ranges into rows for display, so overlapping ranges don't draw over each other.
+    // returns a lane (row) for each range, greedily reusing the first lane with room
+    let mut order: Vec<usize> = (0..ranges.len()).collect();
+    order.sort_by(|a, b| ranges[*a].start.total_cmp(&ranges[*b].start));
+
+    let mut lanes = vec![0; ranges.len()];
+    let mut lane_ends: Vec<Seconds> = vec![];
+    for i in order {
+        let range = &ranges[i];
+        match lane_ends.iter().position(|end| *end < range.start) {
+            Some(lane) => {
+                lanes[i] = lane;
+                lane_ends[lane] = range.end;
+            }
+            None => {
+                lanes[i] = lane_ends.len();
+                lane_ends.push(range.end);
+            }
+        }
+    }
+    return lanes;
+}
+
+#[cfg(test)]
+#[test]
+fn test_overlapping_ranges_are_stacked_in_separate_lanes() {
+    let range = |start: Seconds, end: Seconds| ClipRange {
+        name: "".to_string(),
+        start: start,
+        end: end,
+    };
+    let ranges = vec![
+        range(0.0, 10.0),
+        range(5.0, 15.0),
+        range(12.0, 20.0),
+        range(1.0, 2.0),
+    ];
+    assert_eq!(assign_lanes(&ranges), vec![0, 1, 0, 1]);
+    assert_eq!(assign_lanes(&[]), Vec::<usize>::new());
+}
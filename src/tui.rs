@@ -16,36 +16,98 @@ use crossterm::{
     terminal,
 };
 
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
 use std::{io::stdout, time::Duration};
 
-pub struct Program<Init, View, Update> {
+// set while the terminal is in raw mode and the alternate screen,
+// so panics and signals know whether there's anything to restore
+static TUI_IS_RUNNING: AtomicBool = AtomicBool::new(false);
+
+pub struct Program<Init, View, Update, TickInterval> {
     pub init: Init,
     pub view: View,
     pub update: Update,
+    pub tick_interval: TickInterval, // None means no ticks, so just wait for the next event
+}
+
+// everything update() can receive
+pub enum Msg<AppEvent> {
+    Terminal(Event), // keypresses, resizes
+    Tick,            // scheduled by tick_interval()
+    App(AppEvent),   // sent from other threads with AppEvents
+}
+
+// what the event loop waits on. signals are handled here, never passed to update()
+enum LoopEvent<AppEvent> {
+    Msg(Msg<AppEvent>),
+    Signal(i32),
+}
+
+// lets other threads wake up the event loop, like when a background process has news
+pub struct AppEvents<AppEvent>(mpsc::Sender<LoopEvent<AppEvent>>);
+
+impl<AppEvent> AppEvents<AppEvent> {
+    pub fn send(&self, event: AppEvent) {
+        // fails only after the event loop is gone, when nobody's listening anyway
+        self.0.send(LoopEvent::Msg(Msg::App(event)));
+    }
+}
+
+impl<AppEvent> Clone for AppEvents<AppEvent> {
+    fn clone(&self) -> Self {
+        return AppEvents(self.0.clone());
+    }
 }
 
-pub enum UpdateResult {
+pub enum UpdateResult<E> {
     Continue,
     Finish,
-    Failed(String),
+    Failed(E),
 }
 
-impl<Init, View, Update> Program<Init, View, Update> {
+impl<Init, View, Update, TickInterval> Program<Init, View, Update, TickInterval> {
     // #[tokio::main]
-    pub fn run<Model>(self) -> Result<Model, String>
+    pub fn run<Model, E, AppEvent>(self) -> Result<Model, E>
     where
-        Init: FnOnce() -> Result<Model, String>,
+        AppEvent: Send + 'static,
+        Init: FnOnce(AppEvents<AppEvent>) -> Result<Model, E>,
         View: Fn(&Model, &mut std::io::Stderr),
         // update() mutates the model bc I think it's a bit easier and more performant
         //   than creating a new Model in memory on each update
         //   although maybe returning Model { newfield: _, ..oldmodel } would work fine
-        Update: Fn(&mut Model, Event) -> UpdateResult,
+        Update: Fn(&mut Model, Msg<AppEvent>) -> UpdateResult<E>,
+        TickInterval: Fn(&Model) -> Option<Duration>,
     {
-        let Self { init, view, update } = self;
+        let Self {
+            init,
+            view,
+            update,
+            tick_interval,
+        } = self;
         // write all TUI content to stderr, so other tools can parse stdout on finish
         let mut stderr = std::io::stderr();
 
-        let mut model = init()?; // quit early here if init fails
+        // one channel for everything the event loop waits on:
+        // terminal events from a reader thread, app events from any thread, and signals
+        let (sender, receiver) = mpsc::channel::<LoopEvent<AppEvent>>();
+
+        let mut model = init(AppEvents(sender.clone()))?; // quit early here if init fails
+
+        // a panic would otherwise leave the terminal in raw mode on the alternate screen,
+        // and the panic message would disappear with the alternate screen.
+        // the model is dropped while unwinding, which kills any child processes it owns
+        let default_panic_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            if TUI_IS_RUNNING.load(Ordering::SeqCst) {
+                restore_terminal(&mut std::io::stderr());
+            }
+            default_panic_hook(info);
+        }));
+        TUI_IS_RUNNING.store(true, Ordering::SeqCst);
+        #[cfg(unix)]
+        handle_signals(sender.clone());
+        read_terminal_events(sender);
 
         // disable some behavior like line wrapping and catching Enter presses
         // because i will handle those myself
@@ -114,41 +176,43 @@ impl<Init, View, Update> Program<Init, View, Update> {
         //     }
         // }
 
-        // synchronous method 1)
+        // synchronous method 1), with threads feeding one channel
         //
+        // earlier versions polled for terminal events every 16ms and sent a dummy keypress
+        // on each timeout to trigger a redraw, which kept the cpu busy even while paused.
+        // now the loop sleeps until the next event, or until the next tick if one is scheduled
         loop {
-            // just poll often for terminal events
-            //
-            // while still letting cpu rest a little with this sleep() call.
-            // note that 33ms would be enough for 30fps if each frame is processed instantly
-            //
-            // also note/TODO: this constant polling keeps cpu active even when video is paused.
-            // the alternative is overhauling and refactoring to implement async
-            // (im not ready for that work yet) (esp since i want to avoid heavy tokio dependency)
-            if crossterm::event::poll(Duration::from_millis(16)).unwrap() {
-                // 200, 100, 66, 33, 16
-                let event = crossterm::event::read().unwrap();
-                match update(&mut model, event) {
-                    UpdateResult::Continue => (),
-                    UpdateResult::Finish => break,
-                    UpdateResult::Failed(msg) => {
-                        return Err(msg);
-                        () // to satisfy compiler
-                    }
-                };
-            } else {
-                // no terminal event happened during last N millis.
-                //
-                // send dummy event, just to trigger a redraw
-                // TODO: create a proper 'next tick' event
-                update(
-                    &mut model,
-                    Event::Key(KeyEvent {
-                        code: KeyCode::Char('n'),
-                        modifiers: KeyModifiers::NONE,
-                    }),
-                );
-            }
+            let event = match tick_interval(&model) {
+                Some(interval) => match receiver.recv_timeout(interval) {
+                    Ok(event) => event,
+                    Err(mpsc::RecvTimeoutError::Timeout) => LoopEvent::Msg(Msg::Tick),
+                    Err(mpsc::RecvTimeoutError::Disconnected) => break,
+                },
+                None => match receiver.recv() {
+                    Ok(event) => event,
+                    Err(_) => break,
+                },
+            };
+            let msg = match event {
+                LoopEvent::Msg(msg) => msg,
+                LoopEvent::Signal(signal) => {
+                    // same cleanup as a normal finish, then die from the signal like usual
+                    restore_terminal(&mut stderr);
+                    drop(model);
+                    #[cfg(unix)]
+                    signal_hook::low_level::emulate_default_handler(signal);
+                    std::process::exit(128 + signal); // in case the default is to ignore it
+                }
+            };
+
+            match update(&mut model, msg) {
+                UpdateResult::Continue => (),
+                UpdateResult::Finish => break,
+                UpdateResult::Failed(e) => {
+                    restore_terminal(&mut stderr);
+                    return Err(e);
+                }
+            };
 
             view(&model, &mut stderr);
             stderr.flush();
@@ -172,15 +236,320 @@ impl<Init, View, Update> Program<Init, View, Update> {
         // }
 
         // cleanup and be a good citizen so the terminal behaves normally afterwards (eg. start catching ctrl+c again, and show cursor)
+        restore_terminal(&mut stderr);
+        Ok(model)
+    }
+
+    pub fn run_headless<Model, E, AppEvent>(
+        self,
+        screen: &mut VirtualTerminal,
+        msgs: impl IntoIterator<Item = Msg<AppEvent>>,
+    ) -> Result<Model, E>
+    where
+        Init: FnOnce(AppEvents<AppEvent>) -> Result<Model, E>,
+        View: Fn(&Model, &mut VirtualTerminal),
+        Update: Fn(&mut Model, Msg<AppEvent>) -> UpdateResult<E>,
+    {
+        // like run(), but with scripted msgs instead of a real terminal,
+        // and no threads, signals, or ticks unless the script has them.
+        // so tests can drive update() and then check what view() drew on the screen.
         //
-        // TODO: cleanup even after panic
-        execute!(
-            stderr,
-            terminal::EnableLineWrap,
-            terminal::LeaveAlternateScreen,
-            crossterm::cursor::Show,
-        );
-        terminal::disable_raw_mode();
+        // app events sent from other threads get delivered before the next scripted msg
+        let Self {
+            init, view, update, ..
+        } = self;
+        let (sender, receiver) = mpsc::channel::<LoopEvent<AppEvent>>();
+        let mut model = init(AppEvents(sender))?;
+        view(&model, screen);
+
+        for scripted_msg in msgs {
+            let app_msgs = receiver.try_iter().filter_map(|event| match event {
+                LoopEvent::Msg(msg) => Some(msg),
+                LoopEvent::Signal(_) => None,
+            });
+            for msg in app_msgs
+                .collect::<Vec<_>>()
+                .into_iter()
+                .chain([scripted_msg])
+            {
+                if let Msg::Terminal(Event::Resize(cols, rows)) = msg {
+                    screen.resize(cols, rows);
+                }
+                match update(&mut model, msg) {
+                    UpdateResult::Continue => (),
+                    UpdateResult::Finish => return Ok(model),
+                    UpdateResult::Failed(e) => return Err(e),
+                };
+                view(&model, screen);
+            }
+        }
         Ok(model)
     }
 }
+
+// an in-memory screen for run_headless(), which understands the escape codes that crossterm writes,
+// like cursor movement and clearing, and ignores the rest, like colors.
+//
+// like the real terminal in run(), lines don't wrap,
+// and a newline on the bottom row scrolls everything up
+pub struct VirtualTerminal {
+    cols: u16,
+    rows: u16,
+    cells: Vec<Vec<char>>, // [row][col]
+    cursor: (u16, u16),    // (col, row), zero-based like crossterm's MoveTo
+    pending: Vec<u8>,      // an escape code or utf8 char split across writes
+}
+
+impl VirtualTerminal {
+    pub fn new(cols: u16, rows: u16) -> Self {
+        return Self {
+            cols: cols,
+            rows: rows,
+            cells: vec![vec![' '; cols as usize]; rows as usize],
+            cursor: (0, 0),
+            pending: vec![],
+        };
+    }
+
+    pub fn resize(&mut self, cols: u16, rows: u16) {
+        // keeps whatever fits, from the top left
+        self.cells.resize(rows as usize, vec![]);
+        for row in self.cells.iter_mut() {
+            row.resize(cols as usize, ' ');
+        }
+        self.cols = cols;
+        self.rows = rows;
+        self.move_cursor(self.cursor.0 as i32, self.cursor.1 as i32);
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        // without trailing spaces, for easier comparisons
+        return self
+            .cells
+            .iter()
+            .map(|row| row.iter().collect::<String>().trim_end().to_string())
+            .collect();
+    }
+
+    pub fn text(&self) -> String {
+        return self.lines().join("\n");
+    }
+
+    fn move_cursor(&mut self, col: i32, row: i32) {
+        // clamped to the screen, like a real terminal
+        self.cursor = (
+            col.clamp(0, self.cols.max(1) as i32 - 1) as u16,
+            row.clamp(0, self.rows.max(1) as i32 - 1) as u16,
+        );
+    }
+
+    fn put_char(&mut self, c: char) {
+        if self.cols == 0 || self.rows == 0 {
+            return;
+        }
+        let (col, row) = self.cursor;
+        match c {
+            '\n' => match row + 1 >= self.rows {
+                true => {
+                    self.cells.remove(0);
+                    self.cells.push(vec![' '; self.cols as usize]);
+                }
+                false => self.cursor.1 += 1,
+            },
+            '\r' => self.cursor.0 = 0,
+            _ if c.is_control() => (),
+            _ => {
+                // without line wrap, the last column just keeps getting overwritten
+                self.cells[row as usize][col as usize] = c;
+                self.cursor.0 = std::cmp::min(col + 1, self.cols - 1);
+            }
+        }
+    }
+
+    fn apply_csi(&mut self, params: &str, command: char) {
+        // params like "12;3" in ESC[12;3H
+        //
+        // private modes like ESC[?25l (hide cursor) don't change what's on screen
+        if params.starts_with('?') {
+            return;
+        }
+        let nums = params
+            .split(';')
+            .map(|num| num.parse::<i32>().unwrap_or(0))
+            .collect::<Vec<i32>>();
+        let n = nums.first().copied().unwrap_or(0);
+        let count = n.max(1); // most commands treat 0 or nothing as 1
+        let (col, row) = (self.cursor.0 as i32, self.cursor.1 as i32);
+        match command {
+            'H' => self.move_cursor(nums.get(1).copied().unwrap_or(1) - 1, count - 1),
+            'G' => self.move_cursor(count - 1, row),
+            'd' => self.move_cursor(col, count - 1),
+            'A' => self.move_cursor(col, row - count),
+            'B' => self.move_cursor(col, row + count),
+            'C' => self.move_cursor(col + count, row),
+            'D' => self.move_cursor(col - count, row),
+            'E' => self.move_cursor(0, row + count),
+            'F' => self.move_cursor(0, row - count),
+            'J' => {
+                let first_row = match n {
+                    0 => row as usize + 1, // from the cursor down
+                    _ => 0,                // everything
+                };
+                if n == 0 {
+                    self.cells[row as usize][col as usize..].fill(' ');
+                }
+                for row in self.cells.iter_mut().skip(first_row) {
+                    row.fill(' ');
+                }
+            }
+            'K' => match n {
+                0 => self.cells[row as usize][col as usize..].fill(' '),
+                _ => self.cells[row as usize].fill(' '),
+            },
+            _ => (), // like colors
+        }
+    }
+}
+
+impl Write for VirtualTerminal {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let mut bytes = std::mem::take(&mut self.pending);
+        bytes.extend_from_slice(buf);
+
+        // an incomplete utf8 char at the end waits for the next write
+        let (text, rest) = match std::str::from_utf8(&bytes) {
+            Ok(text) => (text.to_string(), vec![]),
+            Err(e) => (
+                String::from_utf8_lossy(&bytes[..e.valid_up_to()]).to_string(),
+                bytes[e.valid_up_to()..].to_vec(),
+            ),
+        };
+        self.pending = rest;
+
+        let mut chars = text.char_indices();
+        while let Some((i, c)) = chars.next() {
+            if c != '\x1b' {
+                self.put_char(c);
+                continue;
+            }
+            // only CSI codes, like ESC[2J, which is everything crossterm writes for vic
+            let code = &text[i..];
+            match code.chars().nth(1) {
+                Some('[') => (),
+                Some(_) => continue,
+                None => {
+                    self.pending.splice(0..0, code.bytes());
+                    break;
+                }
+            }
+            match code[2..].find(|c: char| ('@'..='~').contains(&c)) {
+                Some(end) => {
+                    let command = code[2 + end..].chars().next().unwrap();
+                    self.apply_csi(&code[2..2 + end], command);
+                    chars.nth(end + 1); // skip past the command
+                }
+                None => {
+                    self.pending.splice(0..0, code.bytes());
+                    break;
+                }
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_virtual_terminal_draws_like_crossterm_says() {
+    let mut screen = VirtualTerminal::new(10, 3);
+    queue!(
+        screen,
+        Print("hello"),
+        crossterm::cursor::MoveToColumn(2), // crossterm 0.23 columns start at 1 here
+        Print("E"),
+        crossterm::cursor::MoveToNextLine(1),
+        crossterm::style::SetForegroundColor(crossterm::style::Color::Red),
+        Print("a line that's too long"),
+        crossterm::style::ResetColor,
+        MoveTo(3, 2),
+        Print("┬"),
+    );
+    assert_eq!(screen.text(), "hEllo\na line thg\n   ┬");
+
+    // a newline on the bottom row scrolls up
+    queue!(screen, Print("\nx"));
+    assert_eq!(screen.lines(), vec!["a line thg", "   ┬", "    x"]);
+
+    // a code split across writes
+    screen.write_all(b"\x1b[2").unwrap();
+    screen.write_all(b"J").unwrap();
+    assert_eq!(screen.text(), "\n\n");
+}
+
+fn restore_terminal(stderr: &mut std::io::Stderr) {
+    TUI_IS_RUNNING.store(false, Ordering::SeqCst);
+    execute!(
+        stderr,
+        terminal::EnableLineWrap,
+        terminal::LeaveAlternateScreen,
+        crossterm::cursor::Show,
+    );
+    terminal::disable_raw_mode();
+}
+
+fn read_terminal_events<AppEvent: Send + 'static>(sender: mpsc::Sender<LoopEvent<AppEvent>>) {
+    // crossterm's read() blocks, so it gets its own thread.
+    // polling with a timeout lets this thread notice when the TUI finishes,
+    // rather than stealing keypresses from whatever runs afterwards
+    std::thread::spawn(move || {
+        while TUI_IS_RUNNING.load(Ordering::SeqCst) {
+            match crossterm::event::poll(Duration::from_millis(100)) {
+                Ok(true) => match crossterm::event::read() {
+                    Ok(event) => {
+                        if sender.send(LoopEvent::Msg(Msg::Terminal(event))).is_err() {
+                            break;
+                        }
+                    }
+                    Err(_) => break,
+                },
+                Ok(false) => (),
+                Err(_) => break,
+            }
+        }
+    });
+}
+
+#[cfg(unix)]
+fn handle_signals<AppEvent: Send + 'static>(sender: mpsc::Sender<LoopEvent<AppEvent>>) {
+    // SIGTERM and SIGHUP (like closing the terminal window) would otherwise kill vic
+    // without restoring the terminal or stopping ffmpeg.
+    // SIGINT too, although raw mode turns ctrl+c into a keypress instead of a signal.
+    //
+    // signal handlers can't safely do much, so this thread just forwards the signal,
+    // and the event loop cleans up instead of running its next update
+    use signal_hook::consts::{SIGHUP, SIGINT, SIGTERM};
+    let mut signals = match signal_hook::iterator::Signals::new([SIGINT, SIGTERM, SIGHUP]) {
+        Ok(signals) => signals,
+        Err(_) => return, // not worth failing over
+    };
+    std::thread::spawn(move || {
+        for signal in signals.forever() {
+            if !TUI_IS_RUNNING.load(Ordering::SeqCst) {
+                // like during exports after the TUI finished
+                signal_hook::low_level::emulate_default_handler(signal);
+                continue;
+            }
+            sender.send(LoopEvent::Signal(signal));
+
+            // in case the event loop is stuck, like waiting on ffmpeg,
+            // at least give the terminal back
+            std::thread::sleep(Duration::from_secs(1));
+            restore_terminal(&mut std::io::stderr());
+            signal_hook::low_level::emulate_default_handler(signal);
+        }
+    });
+}